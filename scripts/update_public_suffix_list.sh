@@ -0,0 +1,20 @@
+#!/usr/bin/env bash
+# Replaces assets/public_suffix_list.dat with the latest upstream list, pinned to its commit,
+# and records that commit in src/public_suffix.rs. Run from the repository root, then `cargo test`.
+set -euo pipefail
+
+REPO=publicsuffix/list
+FILE=public_suffix_list.dat
+
+# The list itself has no version header, so pin the last commit that touched it
+commit=$(curl -fsSL "https://api.github.com/repos/$REPO/commits?path=$FILE&per_page=1" \
+    | grep -m1 -o '"sha": *"[0-9a-f]\{40\}"' | grep -o '[0-9a-f]\{40\}')
+date=$(curl -fsSL "https://api.github.com/repos/$REPO/commits/$commit" \
+    | grep -m1 -o '"date": *"[0-9-]\{10\}' | grep -o '[0-9-]\{10\}$')
+
+curl -fsSL "https://raw.githubusercontent.com/$REPO/$commit/$FILE" -o "assets/$FILE.tmp"
+grep -q '===BEGIN ICANN DOMAINS===' "assets/$FILE.tmp"
+mv "assets/$FILE.tmp" "assets/$FILE"
+
+sed -i "s|^//! Snapshot: .*|//! Snapshot: upstream commit $commit ($date)|" src/public_suffix.rs
+echo "Updated $FILE to $REPO@$commit ($date)"
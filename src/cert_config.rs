@@ -1,5 +1,12 @@
+use std::fmt;
 use std::io;
+use std::net::IpAddr;
+use idna::AsciiDenyList;
+use idna::uts46::{DnsLength, Hyphens, Uts46};
 use crate::CertGenApp;
+use crate::countries;
+use crate::openssl_conf::{OpensslConf, Section, Value};
+use crate::validation;
 
 pub struct CertConfig<'a> {
     pub country: &'a str,
@@ -10,12 +17,821 @@ pub struct CertConfig<'a> {
     pub email: Option<&'a str>,
     pub street_address: Option<&'a str>,
     pub postal_code: Option<&'a str>,
+    pub serial_number: Option<&'a str>,
+    pub title: Option<&'a str>,
+    pub given_name: Option<&'a str>,
+    pub surname: Option<&'a str>,
+    pub pseudonym: Option<&'a str>,
+    pub organization_identifier: Option<&'a str>,
+    pub business_category: Option<&'a str>,
+    /// Domain like `example.com`, written as one DC per label starting with the top level
+    pub domain_component: Option<&'a str>,
+    /// Jurisdiction of incorporation, only used by EV requests
+    pub jurisdiction_country: Option<&'a str>,
+    pub jurisdiction_state: Option<&'a str>,
+    pub jurisdiction_locality: Option<&'a str>,
     pub common_name: &'a str,
-    pub san: &'a Vec<String>,
+    /// Subject in the exact order given by the user, replaces the fixed fields above
+    pub custom_subject: Option<&'a Vec<SubjectEntry>>,
+    /// "ascii" transliterates, "printable" and "utf8" keep the input as typed
+    pub string_encoding: &'a str,
+    pub san: &'a Vec<SanEntry>,
+    pub key_usage: &'a Vec<String>,
+    pub extended_key_usage: &'a Vec<String>,
+    pub custom_extensions: &'a Vec<CustomExtension>,
+    pub ocsp_must_staple: bool,
+    /// ADCS certificate template, either the template name or its OID
+    pub adcs_template: Option<&'a str>,
+    pub adcs_template_major_version: &'a str,
+    pub adcs_template_minor_version: &'a str,
+    pub request_profile: &'a str,
+    pub ca_path_len: &'a str,
+    pub key_algorithm: &'a str,
     pub key_size: &'a str,
+    pub ec_curve: &'a str,
     pub hash_algorithm: &'a str,
+    pub signature_scheme: &'a str,
+    pub pss_salt_length: &'a str,
+    pub pss_mgf1_hash: &'a str,
+    pub existing_key: Option<&'a str>,
+    pub existing_key_passphrase: Option<&'a str>,
+    pub key_passphrase: Option<&'a str>,
+    pub challenge_password: Option<&'a str>,
+    pub unstructured_name: Option<&'a str>,
 }
 
+/// EdDSA keys sign the message directly, so there is no separate hash algorithm to choose
+pub fn is_eddsa(key_algorithm: &str) -> bool {
+    matches!(key_algorithm, "ed25519" | "ed448")
+}
+
+/// Environment variable the generated config reads the challengePassword from, keeping it out of the file
+pub const CHALLENGE_PASSWORD_ENV: &str = "CSR_GENERATOR_CHALLENGE_PASSWORD";
+
+/// Upper bound for the PKCS#9 challengePassword and unstructuredName attributes
+pub const UB_PKCS9_STRING: usize = 255;
+
+/// One attribute of the subject distinguished name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubjectEntry {
+    /// Short name like `OU` or a dotted OID
+    pub attribute: String,
+    pub value: String,
+    /// Joined with the previous entry into one multi-valued RDN, written as `+`
+    pub multi_valued: bool,
+}
+
+impl SubjectEntry {
+    pub fn new(attribute: impl Into<String>, value: impl Into<String>) -> Self {
+        SubjectEntry { attribute: attribute.into(), value: value.into(), multi_valued: false }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let attribute = self.attribute.trim();
+        if attribute.is_empty() || !(attribute.chars().all(|c| c.is_ascii_alphanumeric()) || is_valid_oid(attribute)) {
+            return Err(format!("Invalid subject attribute: {}", self.attribute));
+        }
+        if self.value.trim().is_empty() || self.value.contains('\n') {
+            return Err(format!("Subject attribute {} needs a single line value", attribute));
+        }
+        Ok(())
+    }
+}
+
+/// Subject string encodings as (mode, display name)
+pub const STRING_ENCODINGS: [(&str, &str); 3] = [
+    ("ascii", "ASCII (transliterate umlauts)"),
+    ("printable", "PrintableString where possible"),
+    ("utf8", "UTF8String"),
+];
+
+/// Checks the PrintableString character set (X.680 41.4)
+pub fn is_printable_string(value: &str) -> bool {
+    value.chars().all(|c| c.is_ascii_alphanumeric() || " '()+,-./:=?".contains(c))
+}
+
+/// Attributes suggested in the subject editor, any other short name or OID can be typed in
+pub const SUBJECT_ATTRIBUTES: [&str; 18] = [
+    "C", "ST", "L", "street", "postalCode", "O", "organizationIdentifier", "OU", "businessCategory",
+    "title", "SN", "GN", "pseudonym", "serialNumber", "CN", "emailAddress", "DC", "UID",
+];
+
+/// Long names and OIDs OpenSSL accepts for the subject attributes, as (short name, aliases).
+/// The jurisdiction attributes have no short name in OpenSSL's table and are written by OID.
+const SUBJECT_ATTRIBUTE_ALIASES: [(&str, &[&str]); 22] = [
+    ("C", &["countryName", "2.5.4.6"]),
+    ("ST", &["stateOrProvinceName", "2.5.4.8"]),
+    ("L", &["localityName", "2.5.4.7"]),
+    ("street", &["streetAddress", "2.5.4.9"]),
+    ("postalCode", &["2.5.4.17"]),
+    ("O", &["organizationName", "2.5.4.10"]),
+    ("organizationIdentifier", &["2.5.4.97"]),
+    ("OU", &["organizationalUnitName", "2.5.4.11"]),
+    ("businessCategory", &["2.5.4.15"]),
+    ("title", &["2.5.4.12"]),
+    ("SN", &["surname", "2.5.4.4"]),
+    ("GN", &["givenName", "2.5.4.42"]),
+    ("pseudonym", &["2.5.4.65"]),
+    ("serialNumber", &["2.5.4.5"]),
+    ("dnQualifier", &["2.5.4.46"]),
+    ("CN", &["commonName", "2.5.4.3"]),
+    ("emailAddress", &["1.2.840.113549.1.9.1"]),
+    ("DC", &["domainComponent", "0.9.2342.19200300.100.1.25"]),
+    ("UID", &["userId", "0.9.2342.19200300.100.1.1"]),
+    (JURISDICTION_COUNTRY_OID, &["jurisdictionC", "jurisdictionCountryName"]),
+    (JURISDICTION_STATE_OID, &["jurisdictionST", "jurisdictionStateOrProvinceName"]),
+    (JURISDICTION_LOCALITY_OID, &["jurisdictionL", "jurisdictionLocalityName"]),
+];
+
+/// Short name (or the OID for the jurisdiction attributes) of a subject attribute typed by
+/// long name or OID, e.g. `CN` for `commonName` and `2.5.4.3`. Unknown attributes are returned as is.
+pub fn canonical_attribute(attribute: &str) -> &str {
+    SUBJECT_ATTRIBUTE_ALIASES.iter()
+        .find(|(_, aliases)| aliases.contains(&attribute))
+        .map_or(attribute, |(short, _)| *short)
+}
+
+/// RFC 5280 Appendix A (and X.520) upper bounds in characters, as (attribute, bound, ASN.1 name)
+pub const DN_UPPER_BOUNDS: [(&str, usize, &str); 19] = [
+    ("C", 2, "ub-country-name-alpha-length"),
+    ("ST", 128, "ub-state-name"),
+    ("L", 128, "ub-locality-name"),
+    ("street", 128, "ub-street-address"),
+    ("postalCode", 40, "ub-postal-code"),
+    ("O", 64, "ub-organization-name"),
+    ("OU", 64, "ub-organizational-unit-name"),
+    ("CN", 64, "ub-common-name"),
+    ("emailAddress", 255, "ub-emailaddress-length"),
+    ("serialNumber", 64, "ub-serial-number"),
+    ("title", 64, "ub-title"),
+    ("SN", 32768, "ub-name"),
+    ("GN", 32768, "ub-name"),
+    ("pseudonym", 128, "ub-pseudonym"),
+    ("businessCategory", 128, "ub-business-category"),
+    ("DC", 63, "DNS label length"),
+    (JURISDICTION_COUNTRY_OID, 2, "ub-country-name-alpha-length"),
+    (JURISDICTION_STATE_OID, 128, "ub-state-name"),
+    (JURISDICTION_LOCALITY_OID, 128, "ub-locality-name"),
+];
+
+/// Attributes that are always encoded as PrintableString
+pub const PRINTABLE_STRING_ATTRIBUTES: [&str; 4] = ["C", "serialNumber", "dnQualifier", JURISDICTION_COUNTRY_OID];
+
+/// Attributes that are always encoded as IA5String
+pub const IA5_STRING_ATTRIBUTES: [&str; 2] = ["emailAddress", "DC"];
+
+/// Checks one subject attribute against its RFC 5280 upper bound and mandated character set
+pub fn check_subject_entry(entry: &SubjectEntry) -> Result<(), String> {
+    let attribute = canonical_attribute(entry.attribute.trim());
+    let length = entry.value.chars().count();
+    if let Some((_, bound, name)) = DN_UPPER_BOUNDS.iter().find(|(bounded, _, _)| *bounded == attribute)
+        && length > *bound {
+        return Err(format!("{} is {} characters long, at most {} are allowed ({})", attribute, length, bound, name));
+    }
+    if PRINTABLE_STRING_ATTRIBUTES.contains(&attribute) && !is_printable_string(&entry.value) {
+        return Err(format!("{} may only contain PrintableString characters (A-Z, a-z, 0-9, space and '()+,-./:=?): {}", attribute, entry.value));
+    }
+    if IA5_STRING_ATTRIBUTES.contains(&attribute) && !entry.value.is_ascii() {
+        return Err(format!("{} may only contain ASCII characters: {}", attribute, entry.value));
+    }
+    Ok(())
+}
+
+/// EV jurisdiction of incorporation attributes (EV Guidelines 9.2.4)
+pub const JURISDICTION_COUNTRY_OID: &str = "1.3.6.1.4.1.311.60.2.1.3";
+pub const JURISDICTION_STATE_OID: &str = "1.3.6.1.4.1.311.60.2.1.2";
+pub const JURISDICTION_LOCALITY_OID: &str = "1.3.6.1.4.1.311.60.2.1.1";
+
+/// Values allowed for businessCategory in EV certificates (EV Guidelines 9.2.3)
+pub const EV_BUSINESS_CATEGORIES: [&str; 4] = [
+    "Private Organization",
+    "Government Entity",
+    "Business Entity",
+    "Non-Commercial Entity",
+];
+
+/// Checks a subject against the EV Guidelines subject requirements (9.2),
+/// an error names the attribute that is missing or wrong
+pub fn validate_ev_subject(subject: &[SubjectEntry]) -> Result<(), (&'static str, String)> {
+    let value = |names: &[&str]| subject.iter()
+        .find(|entry| names.contains(&canonical_attribute(entry.attribute.trim())))
+        .map(|entry| entry.value.as_str());
+    let jurisdiction_country = value(&[JURISDICTION_COUNTRY_OID]);
+    let jurisdiction_state = value(&[JURISDICTION_STATE_OID]);
+    let jurisdiction_locality = value(&[JURISDICTION_LOCALITY_OID]);
+
+    if value(&["O"]).is_none() {
+        return Err(("O", "EV requests need an organization name (EV Guidelines 9.2.1)".to_string()));
+    }
+    match value(&["businessCategory"]) {
+        Some(category) if EV_BUSINESS_CATEGORIES.contains(&category) => {}
+        _ => return Err(("businessCategory", format!("EV business category must be one of: {} (EV Guidelines 9.2.3)", EV_BUSINESS_CATEGORIES.join(", ")))),
+    }
+    match jurisdiction_country {
+        Some(country) if country.len() == 2 && country.chars().all(|c| c.is_ascii_uppercase()) => {}
+        _ => return Err((JURISDICTION_COUNTRY_OID, "EV requests need a two letter jurisdiction country (EV Guidelines 9.2.4)".to_string())),
+    }
+    if jurisdiction_locality.is_some() && jurisdiction_state.is_none() {
+        return Err((JURISDICTION_STATE_OID, "A jurisdiction locality also needs the jurisdiction state or province (EV Guidelines 9.2.4)".to_string()));
+    }
+    if value(&["serialNumber"]).is_none() {
+        return Err(("serialNumber", "EV requests need the registration number as serialNumber (EV Guidelines 9.2.5)".to_string()));
+    }
+    if value(&["C"]).is_none() || (value(&["L"]).is_none() && value(&["ST"]).is_none()) {
+        return Err(("L", "EV requests need the country and the locality or state of the place of business (EV Guidelines 9.2.6)".to_string()));
+    }
+    Ok(())
+}
+
+/// Checks the ETSI EN 319 412-1 organizationIdentifier form, e.g. `VATDE-123456789` or `NTRDE-HRB12345`
+/// with a three letter scheme, or `XX:DE-...` for national schemes
+pub fn is_valid_organization_identifier(value: &str) -> bool {
+    let Some((prefix, reference)) = value.split_once('-') else {
+        return false;
+    };
+    let scheme_valid = match prefix.split_once(':') {
+        Some((scheme, country)) => scheme.len() == 2 && country.len() == 2
+            && scheme.chars().chain(country.chars()).all(|c| c.is_ascii_uppercase()),
+        None => prefix.len() == 5 && prefix.chars().all(|c| c.is_ascii_uppercase()),
+    };
+    scheme_valid && !reference.trim().is_empty()
+}
+
+/// OID of the Microsoft User Principal Name otherName
+pub const UPN_OID: &str = "1.3.6.1.4.1.311.20.2.3";
+
+/// Type of a subjectAltName entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SanType {
+    Dns,
+    Ip,
+    Email,
+    Uri,
+    /// Microsoft User Principal Name, an otherName
+    Upn,
+    /// registeredID
+    Rid,
+    /// directoryName, written as `CN=Name, O=Org` or `/CN=Name/O=Org`
+    DirName,
+}
+
+impl SanType {
+    pub const ALL: [SanType; 7] = [
+        SanType::Dns,
+        SanType::Ip,
+        SanType::Email,
+        SanType::Uri,
+        SanType::Upn,
+        SanType::Rid,
+        SanType::DirName,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SanType::Dns => "DNS",
+            SanType::Ip => "IP",
+            SanType::Email => "Email",
+            SanType::Uri => "URI",
+            SanType::Upn => "UPN",
+            SanType::Rid => "RID",
+            SanType::DirName => "DirName",
+        }
+    }
+
+    /// Name prefix in the `[alt_names]` section
+    pub fn config_tag(&self) -> &'static str {
+        match self {
+            SanType::Dns => "DNS",
+            SanType::Ip => "IP",
+            SanType::Email => "email",
+            SanType::Uri => "URI",
+            SanType::Upn => "otherName",
+            SanType::Rid => "RID",
+            SanType::DirName => "dirName",
+        }
+    }
+
+    pub fn hint(&self) -> &'static str {
+        match self {
+            SanType::Dns => "www.example.com",
+            SanType::Ip => "192.168.0.1 or 2001:db8::1",
+            SanType::Email => "admin@example.com",
+            SanType::Uri => "spiffe://example.org/service",
+            SanType::Upn => "user@corp.example.com",
+            SanType::Rid => "1.3.6.1.4.1.99999.1",
+            SanType::DirName => r"CN=Service, O=Example\, Inc.",
+        }
+    }
+
+    /// Guesses the type of a value typed without explicit selection.
+    /// A pasted web address is almost always meant as its host name, it stays a DNS name so validation says so.
+    pub fn detect(value: &str) -> SanType {
+        let lower = value.to_ascii_lowercase();
+        let web_address = lower.starts_with("http://") || lower.starts_with("https://");
+        if value.parse::<IpAddr>().is_ok() {
+            SanType::Ip
+        } else if (value.contains("://") && !web_address) || value.starts_with("urn:") {
+            SanType::Uri
+        } else if value.contains('@') {
+            SanType::Email
+        } else {
+            SanType::Dns
+        }
+    }
+}
+
+/// A single subjectAltName entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SanEntry {
+    pub san_type: SanType,
+    pub value: String,
+}
+
+impl SanEntry {
+    pub fn new(san_type: SanType, value: impl Into<String>) -> Self {
+        SanEntry { san_type, value: value.into() }
+    }
+
+    pub fn detect(value: impl Into<String>) -> Self {
+        let value = value.into();
+        SanEntry { san_type: SanType::detect(&value), value }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let value = self.value.as_str();
+        if value.trim().is_empty() {
+            return Err(format!("{} entry is empty", self.san_type.label()));
+        }
+        let valid = match self.san_type {
+            SanType::Dns => return dns_to_ascii(value).and_then(|ascii| validate_dns_name(&ascii)),
+            SanType::Ip => value.parse::<IpAddr>().is_ok(),
+            // rfc822Name is an IA5String, only the domain can be converted to A-labels (RFC 5280 4.2.1.6)
+            SanType::Email => match value.rsplit_once('@') {
+                Some((local, _)) if !local.is_ascii() => {
+                    return Err(format!("Email {}: the part before @ may only contain ASCII characters", value));
+                }
+                Some((local, domain)) => !local.is_empty() && !domain.is_empty() && dns_to_ascii(domain).is_ok(),
+                None => false,
+            },
+            // The UPN is a UTF8String, any characters are allowed
+            SanType::Upn => value.split_once('@').is_some_and(|(local, domain)| !local.is_empty() && !domain.is_empty()),
+            SanType::Uri if !value.is_ascii() => {
+                return Err(format!("URI {} contains non-ASCII characters, percent-encode them (RFC 3987 3.1)", value));
+            }
+            SanType::Uri => value.split_once(':').is_some_and(|(scheme, rest)| {
+                !rest.is_empty() && scheme.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+                    && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+            }),
+            SanType::Rid => is_valid_oid(value),
+            SanType::DirName => parse_dir_name(value).is_some(),
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(format!("Invalid {} entry: {}", self.san_type.label(), value))
+        }
+    }
+
+    /// Canonical spelling of the entry: DNS names lower-cased without the trailing dot,
+    /// IP addresses in their canonical form (RFC 5952 for IPv6), email and UPN domains lower-cased.
+    /// Values that do not parse are only trimmed, validation reports them.
+    pub fn normalized(&self) -> SanEntry {
+        let value = self.value.trim();
+        let value = match self.san_type {
+            SanType::Dns => value.trim_end_matches('.').to_lowercase(),
+            SanType::Ip => value.parse::<IpAddr>().map_or_else(|_| value.to_string(), |ip| ip.to_string()),
+            // The local part is case sensitive (RFC 5321 2.4), the domain is not
+            SanType::Email | SanType::Upn => match value.rsplit_once('@') {
+                Some((local, domain)) => format!("{}@{}", local, domain.to_lowercase()),
+                None => value.to_string(),
+            },
+            SanType::Uri | SanType::Rid | SanType::DirName => value.to_string(),
+        };
+        SanEntry { san_type: self.san_type, value }
+    }
+
+    /// Whether both entries name the same thing once normalized, DNS names as A-labels so `bücher.de` equals `xn--bcher-kva.de`
+    pub fn is_same(&self, other: &SanEntry) -> bool {
+        self.identity() == other.identity()
+    }
+
+    fn identity(&self) -> SanEntry {
+        let normalized = self.normalized();
+        match normalized.san_type {
+            SanType::Dns => SanEntry::new(SanType::Dns, dns_to_ascii(&normalized.value).unwrap_or(normalized.value).to_ascii_lowercase()),
+            _ => normalized,
+        }
+    }
+
+    /// Whether this is a wildcard DNS name that matches `other`, e.g. `*.example.com` covers `www.example.com`
+    /// but neither `example.com` nor `a.b.example.com` (RFC 6125 6.4.3)
+    pub fn covers(&self, other: &SanEntry) -> bool {
+        if self.san_type != SanType::Dns || other.san_type != SanType::Dns {
+            return false;
+        }
+        let (wildcard, other) = (self.identity().value, other.identity().value);
+        match (wildcard.strip_prefix("*."), other.split_once('.')) {
+            (Some(base), Some((label, rest))) => label != "*" && rest == base,
+            _ => false,
+        }
+    }
+}
+
+/// Longest DNS name without the trailing dot (RFC 1035 2.3.4)
+pub const MAX_DNS_NAME_LEN: usize = 253;
+
+/// Longest DNS label (RFC 1035 2.3.4)
+pub const MAX_DNS_LABEL_LEN: usize = 63;
+
+/// Checks a host name for a dNSName entry: letter-digit-hyphen labels (RFC 1035, A-labels per RFC 5890),
+/// the length limits, and a wildcard only as the whole leftmost label above at least two labels
+pub fn validate_dns_name(name: &str) -> Result<(), String> {
+    match dns_name_problem(name) {
+        Some(problem) => Err(format!("Invalid DNS name {}: {}", name, problem)),
+        None => Ok(()),
+    }
+}
+
+fn dns_name_problem(name: &str) -> Option<String> {
+    // Typical paste mistakes first, they would only show up as odd characters otherwise
+    if name.contains("://") || name.contains('/') {
+        return Some("looks like a URL, enter only the host name".to_string());
+    }
+    if let Some((_, port)) = name.rsplit_once(':') && !port.is_empty() && port.chars().all(|c| c.is_ascii_digit()) {
+        return Some(format!("remove the port :{}", port));
+    }
+    if name.contains('@') {
+        return Some("looks like an email address, use the Email type".to_string());
+    }
+    if name.parse::<IpAddr>().is_ok() {
+        return Some("this is an IP address, use the IP type".to_string());
+    }
+    if name.ends_with('.') {
+        return Some("remove the trailing dot".to_string());
+    }
+    if !name.is_ascii() {
+        return Some("contains characters that are not allowed in domain names".to_string());
+    }
+    if name.len() > MAX_DNS_NAME_LEN {
+        return Some(format!("longer than {} characters", MAX_DNS_NAME_LEN));
+    }
+
+    let labels: Vec<&str> = name.split('.').collect();
+    for (i, label) in labels.iter().enumerate() {
+        if label.is_empty() {
+            return Some("contains an empty label".to_string());
+        }
+        if *label == "*" {
+            if i > 0 {
+                return Some("a wildcard is only allowed as the leftmost label".to_string());
+            }
+            continue;
+        }
+        if label.contains('*') {
+            return Some("a wildcard must be the whole leftmost label, e.g. *.example.com".to_string());
+        }
+        if label.len() > MAX_DNS_LABEL_LEN {
+            return Some(format!("label {} is longer than {} characters", label, MAX_DNS_LABEL_LEN));
+        }
+        if !label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Some(format!("label {} may only contain letters, digits and hyphens", label));
+        }
+        if label.starts_with('-') || label.ends_with('-') {
+            return Some(format!("label {} cannot start or end with a hyphen", label));
+        }
+        // RFC 5891 4.2.3.1, "--" in the third and fourth position is reserved for A-labels
+        if label.get(2..4) == Some("--") && !label.to_ascii_lowercase().starts_with("xn--") {
+            return Some(format!("label {} has -- in the third and fourth position, only xn-- labels may", label));
+        }
+    }
+
+    if labels.last().is_some_and(|tld| tld.chars().all(|c| c.is_ascii_digit())) {
+        return Some("the top-level label cannot be numeric".to_string());
+    }
+    if labels[0] == "*" && labels.len() < 3 {
+        return Some("a wildcard needs at least two labels below it, not a whole top-level domain".to_string());
+    }
+    None
+}
+
+fn has_a_label(name: &str) -> bool {
+    name.split('.').any(|label| label.get(..4).is_some_and(|prefix| prefix.eq_ignore_ascii_case("xn--")))
+}
+
+/// Converts a domain name to the A-label form written to the request (UTS #46 with IDNA 2008 rules),
+/// e.g. `bücher.de` to `xn--bcher-kva.de`. Plain ASCII names are returned unchanged, a leading wildcard is kept.
+pub fn dns_to_ascii(name: &str) -> Result<String, String> {
+    if name.is_ascii() && !has_a_label(name) {
+        return Ok(name.to_string());
+    }
+    let (wildcard, domain) = match name.strip_prefix("*.") {
+        Some(rest) => ("*.", rest),
+        None => ("", name),
+    };
+    Uts46::new().to_ascii(domain.as_bytes(), AsciiDenyList::STD3, Hyphens::Allow, DnsLength::Ignore)
+        .map(|ascii| format!("{}{}", wildcard, ascii))
+        .map_err(|_| format!("Invalid internationalized domain name: {}", name))
+}
+
+/// Unicode form of a name containing A-labels, e.g. `xn--bcher-kva.de` to `bücher.de`
+pub fn dns_to_unicode(name: &str) -> Option<String> {
+    if !has_a_label(name) {
+        return None;
+    }
+    let (wildcard, domain) = match name.strip_prefix("*.") {
+        Some(rest) => ("*.", rest),
+        None => ("", name),
+    };
+    match Uts46::new().to_unicode(domain.as_bytes(), AsciiDenyList::STD3, Hyphens::Allow) {
+        (unicode, Ok(())) => Some(format!("{}{}", wildcard, unicode)),
+        (_, Err(_)) => None,
+    }
+}
+
+/// Why a SAN adds nothing to the request, with the index of the entry that makes it redundant
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SanRedundancy {
+    Duplicate(usize),
+    CoveredBy(usize),
+}
+
+/// Entries that repeat an earlier one after normalization, or are matched by a wildcard in the list
+pub fn san_redundancies(sans: &[SanEntry]) -> Vec<(usize, SanRedundancy)> {
+    let identities: Vec<SanEntry> = sans.iter().map(SanEntry::identity).collect();
+    identities.iter().enumerate()
+        .filter_map(|(i, identity)| {
+            if let Some(first) = identities[..i].iter().position(|earlier| earlier == identity) {
+                return Some((i, SanRedundancy::Duplicate(first)));
+            }
+            sans.iter().position(|wildcard| wildcard.covers(&sans[i]))
+                .map(|wildcard| (i, SanRedundancy::CoveredBy(wildcard)))
+        })
+        .collect()
+}
+
+/// Normalizes every entry and drops the duplicates, keeping the first occurrence.
+/// Names covered by a wildcard are kept, listing them is redundant but not wrong.
+pub fn normalize_sans(sans: &[SanEntry]) -> Vec<SanEntry> {
+    let mut normalized: Vec<SanEntry> = Vec::with_capacity(sans.len());
+    for san in sans {
+        if !normalized.iter().any(|kept| kept.is_same(san)) {
+            normalized.push(san.normalized());
+        }
+    }
+    normalized
+}
+
+/// Keeps the first SAN mirroring the common name after it changed from `previous_cn`.
+/// Only a first entry that mirrored the previous name is replaced, other entries are never overwritten.
+pub fn sync_cn_san(sans: &mut Vec<SanEntry>, previous_cn: &str, common_name: &str) {
+    let previous = SanEntry::new(SanType::Dns, previous_cn);
+    let mirrors_previous = !previous_cn.is_empty() && sans.first().is_some_and(|san| san.is_same(&previous));
+    if mirrors_previous {
+        sans.remove(0);
+    }
+    if !common_name.is_empty() {
+        sans.insert(0, SanEntry::new(SanType::Dns, common_name));
+    }
+}
+
+/// Moves the common name to the front of the SANs, or inserts it there when it is not listed yet
+pub fn mirror_cn_san(sans: &mut Vec<SanEntry>, common_name: &str) {
+    if common_name.trim().is_empty() {
+        return;
+    }
+    let cn = SanEntry::new(SanType::Dns, common_name);
+    sans.retain(|san| !san.is_same(&cn));
+    sans.insert(0, cn);
+}
+
+impl fmt::Display for SanEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.san_type.label(), self.value)
+    }
+}
+
+/// Splits a directory name like `CN=Name, O=Org` or `/CN=Name/O=Org` into its attributes.
+/// Values may escape characters as in RFC 4514, `\,` or `\2C` for a comma (`\/` in the slash form).
+pub fn parse_dir_name(value: &str) -> Option<Vec<(String, String)>> {
+    // Trailing spaces are left to the last value, they may be escaped
+    let value = value.trim_start();
+    let (value, separator) = match value.strip_prefix('/') {
+        Some(rest) => (rest, '/'),
+        None => (value, ','),
+    };
+
+    split_unescaped(value, separator).iter()
+        .map(|part| {
+            let (attribute, value) = part.split_once('=')?;
+            let attribute = attribute.trim();
+            let valid_attribute = !attribute.is_empty()
+                && (attribute.chars().all(|c| c.is_ascii_alphanumeric()) || is_valid_oid(attribute));
+            let value = unescape_dn_value(value)?;
+            (valid_attribute && !value.is_empty()).then(|| (attribute.to_string(), value))
+        })
+        .collect()
+}
+
+/// Splits at every separator that is not escaped with a backslash, the parts keep their escapes
+fn split_unescaped(value: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut chars = value.char_indices();
+    while let Some((i, c)) = chars.next() {
+        if c == '\\' {
+            chars.next();
+        } else if c == separator {
+            parts.push(&value[start..i]);
+            start = i + 1;
+        }
+    }
+    parts.push(&value[start..]);
+    parts
+}
+
+/// Resolves RFC 4514 escapes: a backslash before any character, or before two hex digits of a UTF-8 byte.
+/// Surrounding spaces are dropped unless escaped, `None` for a dangling backslash or invalid hex.
+fn unescape_dn_value(value: &str) -> Option<String> {
+    let value = value.trim_start();
+    let mut value_end = value.trim_end().len();
+    // An escaped trailing space belongs to the value
+    let escapes = value[..value_end].chars().rev().take_while(|c| *c == '\\').count();
+    if escapes % 2 == 1 && value_end < value.len() {
+        value_end += 1;
+    }
+
+    let mut bytes = Vec::new();
+    let mut chars = value[..value_end].chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            bytes.extend(c.to_string().as_bytes());
+            continue;
+        }
+        let escaped = chars.next()?;
+        match chars.peek().copied() {
+            Some(low) if escaped.is_ascii_hexdigit() && low.is_ascii_hexdigit() => {
+                chars.next();
+                bytes.push(u8::from_str_radix(&format!("{}{}", escaped, low), 16).ok()?);
+            }
+            _ => bytes.extend(escaped.to_string().as_bytes()),
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// keyUsage bits as (OpenSSL config name, display name)
+pub const KEY_USAGES: [(&str, &str); 9] = [
+    ("digitalSignature", "Digital Signature"),
+    ("nonRepudiation", "Non Repudiation"),
+    ("keyEncipherment", "Key Encipherment"),
+    ("dataEncipherment", "Data Encipherment"),
+    ("keyAgreement", "Key Agreement"),
+    ("keyCertSign", "Certificate Sign"),
+    ("cRLSign", "CRL Sign"),
+    ("encipherOnly", "Encipher Only"),
+    ("decipherOnly", "Decipher Only"),
+];
+
+/// Well known extendedKeyUsage purposes as (OpenSSL config name, display name), custom OIDs are allowed as well
+pub const EXTENDED_KEY_USAGES: [(&str, &str); 6] = [
+    ("serverAuth", "TLS Server Authentication"),
+    ("clientAuth", "TLS Client Authentication"),
+    ("codeSigning", "Code Signing"),
+    ("emailProtection", "Email Protection"),
+    ("timeStamping", "Time Stamping"),
+    ("OCSPSigning", "OCSP Signing"),
+];
+
+/// Checks for a dotted numeric OID with at least two arcs, e.g. `1.3.6.1.4.1.311`
+pub fn is_valid_oid(oid: &str) -> bool {
+    let arcs: Vec<&str> = oid.split('.').collect();
+    arcs.len() >= 2
+        && arcs.iter().all(|arc| !arc.is_empty() && arc.chars().all(|c| c.is_ascii_digit()))
+        && matches!(arcs[0], "0" | "1" | "2")
+}
+
+/// id-pe-tlsfeature (RFC 7633), carries OCSP Must-Staple
+pub const TLS_FEATURE_OID: &str = "1.3.6.1.5.5.7.1.24";
+
+/// Microsoft Certificate Template Name extension (szOID_ENROLL_CERTTYPE_EXTENSION), a BMPString with the template name
+pub const ADCS_TEMPLATE_NAME_OID: &str = "1.3.6.1.4.1.311.20.2";
+
+/// Microsoft Certificate Template Information extension (szOID_CERTIFICATE_TEMPLATE), template OID and version
+pub const ADCS_TEMPLATE_INFO_OID: &str = "1.3.6.1.4.1.311.21.7";
+
+/// Extensions the request builds itself, custom extensions must not duplicate them
+pub const MANAGED_EXTENSION_OIDS: [(&str, &str); 7] = [
+    ("2.5.29.15", "keyUsage"),
+    ("2.5.29.17", "subjectAltName"),
+    ("2.5.29.19", "basicConstraints"),
+    ("2.5.29.37", "extendedKeyUsage"),
+    (TLS_FEATURE_OID, "tlsfeature"),
+    (ADCS_TEMPLATE_NAME_OID, "certificate template name"),
+    (ADCS_TEMPLATE_INFO_OID, "certificate template information"),
+];
+
+/// How the value of a custom extension is given
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExtensionValueFormat {
+    /// OpenSSL ASN.1 generation string, e.g. `UTF8String:appliance-42` or `INTEGER:5`
+    Asn1,
+    /// DER encoded extension value as hex, e.g. `0c:03:61:62:63`
+    DerHex,
+}
+
+impl ExtensionValueFormat {
+    pub const ALL: [ExtensionValueFormat; 2] = [ExtensionValueFormat::Asn1, ExtensionValueFormat::DerHex];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExtensionValueFormat::Asn1 => "ASN.1",
+            ExtensionValueFormat::DerHex => "DER hex",
+        }
+    }
+
+    pub fn hint(&self) -> &'static str {
+        match self {
+            ExtensionValueFormat::Asn1 => "UTF8String:appliance-42",
+            ExtensionValueFormat::DerHex => "0c:03:61:62:63",
+        }
+    }
+}
+
+/// Extension identified only by its OID, for vendor specific extensions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomExtension {
+    pub oid: String,
+    pub critical: bool,
+    pub format: ExtensionValueFormat,
+    pub value: String,
+}
+
+impl CustomExtension {
+    pub fn new() -> Self {
+        CustomExtension {
+            oid: String::new(),
+            critical: false,
+            format: ExtensionValueFormat::Asn1,
+            value: String::new(),
+        }
+    }
+
+    /// Bytes of a DER hex value, separators `:` and whitespace are ignored
+    pub fn der_bytes(&self) -> Option<Vec<u8>> {
+        let hex: String = self.value.chars().filter(|c| *c != ':' && !c.is_whitespace()).collect();
+        if hex.is_empty() || !hex.len().is_multiple_of(2) {
+            return None;
+        }
+        (0..hex.len()).step_by(2)
+            .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+            .collect()
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let oid = self.oid.trim();
+        if !is_valid_oid(oid) {
+            return Err(format!("Invalid extension OID: {}", self.oid));
+        }
+        if let Some((_, name)) = MANAGED_EXTENSION_OIDS.iter().find(|(managed, _)| *managed == oid) {
+            return Err(format!("Extension {} ({}) is set by its own option, not as a custom extension", oid, name));
+        }
+        let valid_value = match self.format {
+            ExtensionValueFormat::Asn1 => !self.value.trim().is_empty() && !self.value.contains('\n'),
+            ExtensionValueFormat::DerHex => self.der_bytes().is_some(),
+        };
+        if valid_value {
+            Ok(())
+        } else {
+            Err(format!("Invalid {} value for extension {}", self.format.label(), oid))
+        }
+    }
+
+    /// Value as written in the config, using OpenSSL's generic `ASN1:` and `DER:` forms
+    pub fn config_value(&self) -> String {
+        let value = match self.format {
+            ExtensionValueFormat::Asn1 => format!("ASN1:{}", self.value.trim()),
+            ExtensionValueFormat::DerHex => {
+                let hex: Vec<String> = self.der_bytes().unwrap_or_default().iter().map(|b| format!("{:02X}", b)).collect();
+                format!("DER:{}", hex.join(":"))
+            }
+        };
+        if self.critical { format!("critical, {}", value) } else { value }
+    }
+}
+
+impl Default for CustomExtension {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Curves offered for ECDSA keys as (OpenSSL curve name, NIST name)
+pub const EC_CURVES: [(&str, &str); 3] = [
+    ("prime256v1", "P-256"),
+    ("secp384r1", "P-384"),
+    ("secp521r1", "P-521"),
+];
+
 impl<'a> From<&'a CertGenApp> for CertConfig<'a> {
     fn from(value: &'a CertGenApp) -> Self {
         CertConfig {
@@ -27,10 +843,42 @@ impl<'a> From<&'a CertGenApp> for CertConfig<'a> {
             email: if value.email.is_empty() { None } else { Some(&value.email) },
             street_address: if value.street_address.is_empty() { None } else { Some(&value.street_address) },
             postal_code: if value.postal_code.is_empty() { None } else { Some(&value.postal_code) },
+            serial_number: if value.serial_number.is_empty() { None } else { Some(&value.serial_number) },
+            title: if value.title.is_empty() { None } else { Some(&value.title) },
+            given_name: if value.given_name.is_empty() { None } else { Some(&value.given_name) },
+            surname: if value.surname.is_empty() { None } else { Some(&value.surname) },
+            pseudonym: if value.pseudonym.is_empty() { None } else { Some(&value.pseudonym) },
+            organization_identifier: if value.organization_identifier.is_empty() { None } else { Some(&value.organization_identifier) },
+            business_category: if value.business_category.is_empty() { None } else { Some(&value.business_category) },
+            domain_component: if value.domain_component.is_empty() { None } else { Some(&value.domain_component) },
+            jurisdiction_country: if value.is_ev_request() && !value.jurisdiction_country.is_empty() { Some(&value.jurisdiction_country) } else { None },
+            jurisdiction_state: if value.is_ev_request() && !value.jurisdiction_state.is_empty() { Some(&value.jurisdiction_state) } else { None },
+            jurisdiction_locality: if value.is_ev_request() && !value.jurisdiction_locality.is_empty() { Some(&value.jurisdiction_locality) } else { None },
             common_name: &value.common_name,
+            custom_subject: if value.uses_custom_subject() { Some(&value.subject) } else { None },
+            string_encoding: if value.advanced_mode { &value.string_encoding } else { "ascii" },
             san: &value.sans,
+            key_usage: &value.key_usage,
+            extended_key_usage: &value.extended_key_usage,
+            custom_extensions: &value.custom_extensions,
+            ocsp_must_staple: value.advanced_mode && value.ocsp_must_staple,
+            adcs_template: if value.advanced_mode && !value.adcs_template.trim().is_empty() { Some(value.adcs_template.trim()) } else { None },
+            adcs_template_major_version: value.adcs_template_major_version.trim(),
+            adcs_template_minor_version: value.adcs_template_minor_version.trim(),
+            request_profile: if value.advanced_mode { &value.request_profile } else { "end_entity" },
+            ca_path_len: &value.ca_path_len,
+            key_algorithm: &value.key_algorithm,
             key_size: &value.key_size,
+            ec_curve: &value.ec_curve,
             hash_algorithm: &value.hash_algorithm,
+            signature_scheme: &value.signature_scheme,
+            pss_salt_length: &value.pss_salt_length,
+            pss_mgf1_hash: &value.pss_mgf1_hash,
+            existing_key: if value.reuses_existing_key() { Some(&value.existing_key_path) } else { None },
+            existing_key_passphrase: if value.existing_key_passphrase.is_empty() { None } else { Some(&value.existing_key_passphrase) },
+            key_passphrase: if value.encrypts_new_key() { Some(&value.key_passphrase) } else { None },
+            challenge_password: if value.advanced_mode && !value.challenge_password.is_empty() { Some(&value.challenge_password) } else { None },
+            unstructured_name: if value.advanced_mode && !value.unstructured_name.is_empty() { Some(&value.unstructured_name) } else { None },
         }
     }
 }
@@ -182,94 +1030,957 @@ fn sanitize_internal(input: &str, preserve_spaces: bool) -> String {
 
 impl<'a> CertConfig<'a> {
 
-    pub fn generate_config(&self) -> io::Result<String> {
-        // Validate country code is two letters
-        if self.country.len() != 2 {
-            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Country code must be exactly 2 letters"));
+    /// Name used for the generated files, wildcards are spelled out since `*` is not a valid file name character.
+    /// CA names like "Example Issuing CA" are sanitized as well.
+    pub fn file_name(&self) -> String {
+        if self.common_name.starts_with("*.") {
+            sanitize(&self.common_name.replacen("*.", "wildcard.", 1))
+        } else {
+            sanitize(self.common_name)
         }
+    }
 
-        // Generate configuration content
-        let mut config_content = String::new();
+    pub fn is_ca_request(&self) -> bool {
+        self.request_profile == "ca"
+    }
 
-        // Basic configuration
-        config_content.push_str("[req]\n");
-        config_content.push_str("distinguished_name = req_distinguished_name\n");
-        config_content.push_str(&format!("default_bits = {}\n", self.key_size));
-        config_content.push_str("prompt = no\n");
-        config_content.push_str(&format!("default_md = {}\n", self.hash_algorithm));
-        config_content.push_str("encrypt_key = no\n");      // Equivalent to -nodes option
-        let keyfile_name = if self.common_name.starts_with("*.") {
-            self.common_name.replacen("*.", "wildcard.", 1)
-        } else {
-            self.common_name.to_string()
-        };
+    pub fn is_ev_request(&self) -> bool {
+        self.request_profile == "ev"
+    }
 
-        config_content.push_str(&format!("default_keyfile = {}.key\n", keyfile_name));
+    /// Requested key usages, CA requests always ask for certificate and CRL signing
+    pub fn effective_key_usage(&self) -> Vec<&str> {
+        let mut usages: Vec<&str> = self.key_usage.iter().map(String::as_str).collect();
+        if self.is_ca_request() {
+            for usage in ["keyCertSign", "cRLSign"] {
+                if !usages.contains(&usage) {
+                    usages.push(usage);
+                }
+            }
+        }
+        usages
+    }
 
-        if !self.san.is_empty() {
-            config_content.push_str("req_extensions = v3_req\n");
+    /// Arguments for the `openssl req` invocation that creates the CSR from the given config file.
+    /// RSA keys are sized by `default_bits` in the config, other key types need `-newkey`.
+    /// An existing key is passed with `-key` instead of generating a new one.
+    /// New keys with a passphrase are encrypted with AES-256, the passphrase itself is never part of the arguments.
+    pub fn req_args(&self, config_file: &str) -> Vec<String> {
+        let mut args: Vec<String> = vec!["req".into(), "-new".into()];
+        if let Some(key_path) = self.existing_key {
+            args.extend(["-key".into(), key_path.to_string()]);
+        }
+        args.extend([
+            "-out".into(), format!("{}.csr", self.file_name()),
+            "-config".into(), config_file.to_string(),
+        ]);
+        if self.existing_key.is_none() {
+            match self.key_algorithm {
+                "ec" => args.extend([
+                    "-newkey".into(), "ec".into(),
+                    "-pkeyopt".into(), format!("ec_paramgen_curve:{}", self.ec_curve),
+                ]),
+                "ed25519" | "ed448" => args.extend(["-newkey".into(), self.key_algorithm.to_string()]),
+                _ => {}
+            }
+            if self.key_passphrase.is_some() {
+                args.extend(["-cipher".into(), "aes-256-cbc".into()]);
+            }
         }
+        args.extend(self.sigopts().into_iter().flat_map(|opt| ["-sigopt".to_string(), opt]));
+        args
+    }
 
-        // Distinguished name section
-        config_content.push_str("\n[req_distinguished_name]\n");
-        config_content.push_str(&format!("C = {}\n", self.country));
-        config_content.push_str(&format!("ST = {}\n", sanitize_for_cert_field(self.state)));
-        config_content.push_str(&format!("L = {}\n", sanitize_for_cert_field(self.locality)));
+    /// Signature options for RSASSA-PSS, these can only be passed on the command line
+    pub fn sigopts(&self) -> Vec<String> {
+        if self.signature_scheme != "pss" {
+            return Vec::new();
+        }
+        vec![
+            "rsa_padding_mode:pss".to_string(),
+            format!("rsa_pss_saltlen:{}", self.pss_salt_length),
+            format!("rsa_mgf1_md:{}", self.pss_mgf1_hash),
+        ]
+    }
 
-        // Optional street address and postal code
-        if let Some(street) = self.street_address {
-            if !street.trim().is_empty() {
-                config_content.push_str(&format!("street = {}\n", sanitize_for_cert_field(street)));
+    /// `openssl req` command line for display and for the recreate command in the zip
+    pub fn req_command(&self, config_file: &str) -> String {
+        let mut command = String::from("openssl");
+        for arg in self.req_args(config_file) {
+            if arg.contains(char::is_whitespace) {
+                command.push_str(&format!(" \"{}\"", arg));
+            } else {
+                command.push(' ');
+                command.push_str(&arg);
             }
         }
+        command
+    }
+
+    /// Whether the request carries any v3 extensions
+    pub fn has_extensions(&self) -> bool {
+        !self.san.is_empty() || !self.key_usage.is_empty() || !self.extended_key_usage.is_empty() || self.is_ca_request()
+            || !self.custom_extensions.is_empty() || self.ocsp_must_staple || self.adcs_template.is_some()
+    }
+
+    /// ADCS templates given by OID use the template information extension, names the template name extension
+    pub fn adcs_template_oid(&self) -> Option<&str> {
+        self.adcs_template.filter(|template| is_valid_oid(template))
+    }
 
-        if let Some(postal) = self.postal_code {
-            if !postal.trim().is_empty() {
-                config_content.push_str(&format!("postalCode = {}\n", postal));
+    /// Subject built from the fixed fields in the traditional order, unsanitized.
+    /// Domain components come first, most significant label first.
+    pub fn field_subject(&self) -> Vec<SubjectEntry> {
+        let mut entries: Vec<SubjectEntry> = self.domain_component
+            .map(|domain| domain.trim().trim_end_matches('.').rsplit('.').map(|label| SubjectEntry::new("DC", label)).collect())
+            .unwrap_or_default();
+        entries.extend([
+            SubjectEntry::new("C", self.country),
+            SubjectEntry::new("ST", self.state),
+            SubjectEntry::new("L", self.locality),
+        ]);
+        let optional = |attribute: &str, value: Option<&str>| {
+            value.filter(|value| !value.trim().is_empty()).map(|value| SubjectEntry::new(attribute, value))
+        };
+        entries.extend(optional("street", self.street_address));
+        entries.extend(optional("postalCode", self.postal_code));
+        entries.push(SubjectEntry::new("O", self.organization));
+        entries.extend(optional("organizationIdentifier", self.organization_identifier));
+        entries.extend(optional("OU", self.organizational_unit));
+        entries.extend(optional("businessCategory", self.business_category));
+        entries.extend(optional(JURISDICTION_COUNTRY_OID, self.jurisdiction_country));
+        entries.extend(optional(JURISDICTION_STATE_OID, self.jurisdiction_state));
+        entries.extend(optional(JURISDICTION_LOCALITY_OID, self.jurisdiction_locality));
+        entries.extend(optional("title", self.title));
+        entries.extend(optional("SN", self.surname));
+        entries.extend(optional("GN", self.given_name));
+        entries.extend(optional("pseudonym", self.pseudonym));
+        entries.extend(optional("serialNumber", self.serial_number));
+        entries.push(SubjectEntry::new("CN", self.common_name));
+        entries.extend(optional("emailAddress", self.email));
+        entries
+    }
+
+    /// SAN entries as both backends write them: normalized without duplicates,
+    /// internationalized DNS names and email domains as A-labels
+    pub fn san_entries(&self) -> Vec<SanEntry> {
+        normalize_sans(self.san).into_iter()
+            .map(|san| match san.san_type {
+                SanType::Dns => SanEntry::new(SanType::Dns, dns_to_ascii(&san.value).unwrap_or(san.value)),
+                SanType::Email => match san.value.rsplit_once('@').map(|(local, domain)| (local, dns_to_ascii(domain))) {
+                    Some((local, Ok(domain))) => SanEntry::new(SanType::Email, format!("{}@{}", local, domain)),
+                    _ => san,
+                },
+                _ => san,
+            })
+            .collect()
+    }
+
+    /// Subject entries in request order, as both backends write them.
+    /// Attributes typed by long name or OID are written by their short name.
+    /// The first CN of a custom subject is taken from the common name field.
+    /// In "ascii" mode values are transliterated, except CN and email which are never changed.
+    /// Country codes are upper-cased.
+    pub fn subject_entries(&self) -> Vec<SubjectEntry> {
+        let mut entries = match self.custom_subject {
+            Some(custom) => {
+                let mut entries = custom.clone();
+                for entry in entries.iter_mut() {
+                    entry.attribute = canonical_attribute(entry.attribute.trim()).to_string();
+                }
+                if let Some(cn) = entries.iter_mut().find(|entry| entry.attribute == "CN") {
+                    cn.value = self.common_name.to_string();
+                }
+                entries
+            }
+            None => self.field_subject(),
+        };
+
+        for entry in entries.iter_mut() {
+            entry.attribute = entry.attribute.trim().to_string();
+            // A CN that repeats a DNS SAN is written the same way: normalized, internationalized names as A-labels
+            if entry.attribute == "CN" {
+                let cn = SanEntry::new(SanType::Dns, entry.value.as_str()).identity();
+                if self.san.iter().any(|san| san.identity() == cn) {
+                    entry.value = cn.value;
+                }
+            }
+            if self.string_encoding == "ascii" && !matches!(entry.attribute.as_str(), "CN" | "emailAddress") {
+                entry.value = sanitize_for_cert_field(&entry.value);
+            }
+            if matches!(entry.attribute.as_str(), "C" | JURISDICTION_COUNTRY_OID) {
+                entry.value = countries::normalize(&entry.value);
             }
         }
+        entries
+    }
 
-        config_content.push_str(&format!("O = {}\n", sanitize_for_cert_field(self.organization)));
 
-        // Optional OU
-        if let Some(ou) = self.organizational_unit {
-            if !ou.trim().is_empty() {
-                config_content.push_str(&format!("OU = {}\n", sanitize_for_cert_field(ou)));
+    /// Whether the request carries PKCS#9 attributes besides the extension request
+    pub fn has_attributes(&self) -> bool {
+        self.challenge_password.is_some() || self.unstructured_name.is_some()
+    }
+
+    pub fn generate_config(&self) -> io::Result<String> {
+        // Report every problem at once, warnings do not block the config
+        validation::ensure_valid(self)?;
+
+        let subject = self.subject_entries();
+        let mut conf = OpensslConf::default();
+
+        // Basic configuration
+        let req = conf.section("req");
+        req.entry("distinguished_name", "req_distinguished_name");
+        if self.key_algorithm == "rsa" && self.existing_key.is_none() {
+            req.entry("default_bits", self.key_size);
+        }
+        req.entry("prompt", "no");
+        // Without a string_mask OpenSSL writes UTF8String
+        match self.string_encoding {
+            "printable" => {
+                req.comment("PrintableString where possible, UTF8String otherwise");
+                req.entry("string_mask", "MASK:0x2002");
+            }
+            "utf8" => {
+                req.entry("string_mask", "utf8only");
             }
+            _ => {}
+        }
+        // The input is only read as UTF-8 with utf8 = yes, even in ascii mode CN and emailAddress are kept as entered
+        req.entry("utf8", "yes");
+        if self.existing_key.is_some() || !is_eddsa(self.key_algorithm) {
+            req.entry("default_md", self.hash_algorithm);
+        }
+        // The config has no setting for the signature scheme, document the required options instead
+        for opt in self.sigopts() {
+            req.comment(format!("requires -sigopt {}", opt));
+        }
+        if self.existing_key.is_none() {
+            // encrypt_key = no is equivalent to the -nodes option
+            req.entry("encrypt_key", if self.key_passphrase.is_some() { "yes" } else { "no" });
+            req.entry("default_keyfile", format!("{}.key", self.file_name()));
+        }
+        if self.has_extensions() {
+            req.entry("req_extensions", "v3_req");
+        }
+        if self.has_attributes() {
+            req.entry("attributes", "req_attributes");
         }
 
-        config_content.push_str(&format!("CN = {}\n", self.common_name));
+        // Distinguished name section. OpenSSL skips everything up to the first '.' of a name,
+        // which keeps repeated attributes apart, and a leading '+' joins the previous RDN.
+        let distinguished_name = conf.section("req_distinguished_name");
+        for (i, entry) in subject.iter().enumerate() {
+            let repeated = subject[..i].iter().any(|previous| previous.attribute == entry.attribute);
+            let multi_valued = if entry.multi_valued { "+" } else { "" };
+            if repeated || entry.multi_valued || is_valid_oid(&entry.attribute) {
+                distinguished_name.entry(format!("{}.{}{}", i, multi_valued, entry.attribute), entry.value.as_str());
+            } else {
+                distinguished_name.entry(entry.attribute.as_str(), entry.value.as_str());
+            }
+        }
 
-        if let Some(email_addr) = self.email {
-            if !email_addr.trim().is_empty() {
-                config_content.push_str(&format!("emailAddress = {}\n", email_addr));
+        if self.has_attributes() {
+            let attributes = conf.section("req_attributes");
+            if self.challenge_password.is_some() {
+                attributes.comment(format!("Set {} to the challenge password when running openssl req", CHALLENGE_PASSWORD_ENV));
+                attributes.entry("challengePassword", Value::variable(Some("ENV"), CHALLENGE_PASSWORD_ENV));
+            }
+            if let Some(name) = self.unstructured_name {
+                attributes.entry("unstructuredName", name);
             }
         }
 
-        if !self.san.is_empty() {
+        // The template information extension references its own section, written after the extensions
+        let mut template_section = None;
+        let san = self.san_entries();
+        if self.has_extensions() {
             // Extensions section
-            config_content.push_str("[v3_req]\n");
-            config_content.push_str("subjectAltName = @alt_names\n\n");
-
-            // Alternative names section
-            config_content.push_str("[alt_names]\n");
-            for (i, san) in self.san.iter().enumerate() {
-                // Check if it's an IP address or DNS name (simple check)
-                if san.parse::<std::net::IpAddr>().is_ok() {
-                    config_content.push_str(&format!("IP.{} = {}\n", i + 1, san));
+            let extensions = conf.section("v3_req");
+            if self.is_ca_request() {
+                if self.ca_path_len.is_empty() {
+                    extensions.entry("basicConstraints", "critical, CA:TRUE");
                 } else {
-                    config_content.push_str(&format!("DNS.{} = {}\n", i + 1, san));
+                    extensions.entry("basicConstraints", format!("critical, CA:TRUE, pathlen:{}", self.ca_path_len));
                 }
             }
+            let key_usage = self.effective_key_usage();
+            if !key_usage.is_empty() {
+                extensions.entry("keyUsage", format!("critical, {}", key_usage.join(", ")));
+            }
+            if !self.extended_key_usage.is_empty() {
+                extensions.entry("extendedKeyUsage", self.extended_key_usage.join(", "));
+            }
+            if self.ocsp_must_staple {
+                extensions.entry("tlsfeature", "status_request");
+            }
+            if let Some(template_oid) = self.adcs_template_oid() {
+                extensions.entry(ADCS_TEMPLATE_INFO_OID, "ASN1:SEQUENCE:adcs_template");
+                let mut template = Section::new("adcs_template");
+                template.entry("templateID", format!("OID:{}", template_oid));
+                if !self.adcs_template_major_version.is_empty() {
+                    template.entry("majorVersion", format!("INTEGER:{}", self.adcs_template_major_version));
+                }
+                if !self.adcs_template_minor_version.is_empty() {
+                    template.entry("minorVersion", format!("INTEGER:{}", self.adcs_template_minor_version));
+                }
+                template_section = Some(template);
+            } else if let Some(template_name) = self.adcs_template {
+                extensions.entry(ADCS_TEMPLATE_NAME_OID, format!("ASN1:BMPString:{}", template_name));
+            }
+            for extension in self.custom_extensions {
+                extensions.entry(extension.oid.trim(), extension.config_value());
+            }
+            if !san.is_empty() {
+                extensions.entry("subjectAltName", Value::section_ref("alt_names"));
+            }
+        }
+
+        if !san.is_empty() {
+            // Alternative names section, directory names reference their own section
+            let mut dir_sections = Vec::new();
+            let alt_names = conf.section("alt_names");
+            for (i, san) in san.iter().enumerate() {
+                let key = format!("{}.{}", san.san_type.config_tag(), i + 1);
+                match san.san_type {
+                    SanType::Upn => {
+                        alt_names.entry(key, format!("{};UTF8:{}", UPN_OID, san.value));
+                    }
+                    SanType::DirName => {
+                        let mut dir_section = Section::new(format!("dir_sect_{}", i + 1));
+                        for (attribute, value) in parse_dir_name(&san.value).unwrap_or_default() {
+                            dir_section.entry(attribute, value);
+                        }
+                        alt_names.entry(key, dir_section.name.as_str());
+                        dir_sections.push(dir_section);
+                    }
+                    _ => {
+                        alt_names.entry(key, san.value.as_str());
+                    }
+                }
+            }
+            conf.sections.extend(dir_sections);
+        }
+        conf.sections.extend(template_section);
+
+        // Correct by construction, but a config OpenSSL would read differently must never reach it
+        let dangling = conf.dangling_references();
+        if !dangling.is_empty() {
+            return Err(io::Error::other(dangling.join("\n")));
+        }
+        let config_content = conf.to_string();
+        if OpensslConf::parse(&config_content).as_ref() != Ok(&conf) {
+            return Err(io::Error::other("The generated config does not read back as written"));
         }
         Ok(config_content)
     }
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
 
+    /// Minimal valid config, tests override the fields they care about
+    pub(crate) fn test_config(san: &Vec<SanEntry>) -> CertConfig<'_> {
+        static NO_USAGES: Vec<String> = Vec::new();
+        static NO_EXTENSIONS: Vec<CustomExtension> = Vec::new();
+
+        CertConfig {
+            country: "DE",
+            state: "Nordrhein-Westfalen",
+            locality: "Münster",
+            organization: "Test Inc.",
+            organizational_unit: None,
+            email: None,
+            street_address: None,
+            postal_code: None,
+            serial_number: None,
+            title: None,
+            given_name: None,
+            surname: None,
+            pseudonym: None,
+            organization_identifier: None,
+            business_category: None,
+            domain_component: None,
+            jurisdiction_country: None,
+            jurisdiction_state: None,
+            jurisdiction_locality: None,
+            common_name: "test.example.com",
+            custom_subject: None,
+            string_encoding: "ascii",
+            san,
+            key_usage: &NO_USAGES,
+            extended_key_usage: &NO_USAGES,
+            custom_extensions: &NO_EXTENSIONS,
+            ocsp_must_staple: false,
+            adcs_template: None,
+            adcs_template_major_version: "",
+            adcs_template_minor_version: "",
+            request_profile: "end_entity",
+            ca_path_len: "",
+            key_algorithm: "rsa",
+            key_size: "2048",
+            ec_curve: "prime256v1",
+            hash_algorithm: "sha256",
+            signature_scheme: "pkcs1",
+            pss_salt_length: "digest",
+            pss_mgf1_hash: "sha256",
+            existing_key: None,
+            existing_key_passphrase: None,
+            key_passphrase: None,
+            challenge_password: None,
+            unstructured_name: None,
+        }
+    }
+
+    #[test]
+    fn test_generate_config_rsa_sets_default_bits() {
+        let san = vec![SanEntry::detect("test.example.com")];
+        let config = test_config(&san).generate_config().unwrap();
+        assert!(config.contains("default_bits = 2048\n"));
+        assert_eq!(test_config(&san).req_command("test.cnf"), "openssl req -new -out test.example.com.csr -config test.cnf");
+    }
+
+    #[test]
+    fn test_generate_config_ec_uses_newkey() {
+        let san = vec![SanEntry::detect("test.example.com")];
+        let mut config = test_config(&san);
+        config.key_algorithm = "ec";
+        config.ec_curve = "secp384r1";
+        assert!(!config.generate_config().unwrap().contains("default_bits"));
+        assert_eq!(
+            config.req_command("test.cnf"),
+            "openssl req -new -out test.example.com.csr -config test.cnf -newkey ec -pkeyopt ec_paramgen_curve:secp384r1"
+        );
+    }
+
+    #[test]
+    fn test_generate_config_eddsa_has_no_digest() {
+        let san = vec![SanEntry::detect("test.example.com")];
+        let mut config = test_config(&san);
+        config.key_algorithm = "ed25519";
+        let text = config.generate_config().unwrap();
+        assert!(!text.contains("default_md"));
+        assert!(!text.contains("default_bits"));
+        assert!(config.req_command("test.cnf").ends_with(" -newkey ed25519"));
+    }
+
+    #[test]
+    fn test_generate_config_existing_key() {
+        let san = vec![SanEntry::detect("test.example.com")];
+        let mut config = test_config(&san);
+        config.key_algorithm = "ec";
+        config.existing_key = Some("/home/user/old keys/test.key");
+        let text = config.generate_config().unwrap();
+        assert!(!text.contains("default_keyfile"));
+        assert!(!text.contains("default_bits"));
+        assert_eq!(
+            config.req_command("test.cnf"),
+            "openssl req -new -key \"/home/user/old keys/test.key\" -out test.example.com.csr -config test.cnf"
+        );
+    }
+
+    #[test]
+    fn test_generate_config_encrypted_key() {
+        let san = vec![SanEntry::detect("test.example.com")];
+        let mut config = test_config(&san);
+        config.key_passphrase = Some("correct horse battery staple");
+        let text = config.generate_config().unwrap();
+        assert!(text.contains("encrypt_key = yes\n"));
+        assert!(!text.contains("correct horse"));
+        let command = config.req_command("test.cnf");
+        assert!(command.ends_with(" -cipher aes-256-cbc"));
+        assert!(!command.contains("correct horse"));
+    }
+
+    #[test]
+    fn test_generate_config_rsa_pss() {
+        let san = vec![SanEntry::detect("test.example.com")];
+        let mut config = test_config(&san);
+        config.signature_scheme = "pss";
+        config.pss_salt_length = "32";
+        config.pss_mgf1_hash = "sha384";
+        assert!(config.generate_config().unwrap().contains("# requires -sigopt rsa_padding_mode:pss\n"));
+        assert!(config.req_command("test.cnf").ends_with(
+            " -sigopt rsa_padding_mode:pss -sigopt rsa_pss_saltlen:32 -sigopt rsa_mgf1_md:sha384"
+        ));
+
+        config.pss_salt_length = "lots";
+        assert!(config.generate_config().is_err());
+
+        config.pss_salt_length = "digest";
+        config.key_algorithm = "ec";
+        assert!(config.generate_config().is_err());
+    }
+
+    #[test]
+    fn test_generate_config_key_usage() {
+        let san = vec![SanEntry::detect("test.example.com")];
+        let key_usage = vec!["digitalSignature".to_string(), "keyEncipherment".to_string()];
+        let extended_key_usage = vec!["serverAuth".to_string(), "1.3.6.1.4.1.311.10.3.4".to_string()];
+        let mut config = test_config(&san);
+        config.key_usage = &key_usage;
+        config.extended_key_usage = &extended_key_usage;
+
+        let text = config.generate_config().unwrap();
+        assert!(text.contains("req_extensions = v3_req\n"));
+        assert!(text.contains("\n\n[v3_req]\nkeyUsage = critical, digitalSignature, keyEncipherment\n"));
+        assert!(text.contains("extendedKeyUsage = serverAuth, 1.3.6.1.4.1.311.10.3.4\n"));
+        assert!(text.contains("subjectAltName = @alt_names\n"));
+
+        let bogus = vec!["serverAuth".to_string(), "1.3.x".to_string()];
+        config.extended_key_usage = &bogus;
+        assert!(config.generate_config().is_err());
+    }
+
+    #[test]
+    fn test_generate_config_ca_request() {
+        let san = Vec::new();
+        let key_usage = vec!["digitalSignature".to_string()];
+        let mut config = test_config(&san);
+        config.common_name = "Example Issuing CA 1";
+        config.request_profile = "ca";
+        config.ca_path_len = "0";
+        config.key_usage = &key_usage;
+
+        let text = config.generate_config().unwrap();
+        assert!(text.contains("req_extensions = v3_req\n"));
+        assert!(text.contains("basicConstraints = critical, CA:TRUE, pathlen:0\n"));
+        assert!(text.contains("keyUsage = critical, digitalSignature, keyCertSign, cRLSign\n"));
+        assert!(!text.contains("subjectAltName"));
+        assert!(text.contains("default_keyfile = Example-Issuing-CA-1.key\n"));
+
+        config.ca_path_len = "";
+        assert!(config.generate_config().unwrap().contains("basicConstraints = critical, CA:TRUE\n"));
+
+        config.ca_path_len = "-1";
+        assert!(config.generate_config().is_err());
+    }
+
+    #[test]
+    fn test_generate_config_san_types() {
+        let san = vec![
+            SanEntry::detect("test.example.com"),
+            SanEntry::detect("10.0.0.1"),
+            SanEntry::detect("admin@example.com"),
+            SanEntry::detect("spiffe://example.org/ns/prod/sa/web"),
+            SanEntry::new(SanType::Upn, "user@corp.example.com"),
+            SanEntry::new(SanType::Rid, "1.3.6.1.4.1.99999.1"),
+            SanEntry::new(SanType::DirName, "/C=DE/O=Test Inc./CN=Service"),
+        ];
+        let text = test_config(&san).generate_config().unwrap();
+        assert!(text.contains("[alt_names]\nDNS.1 = test.example.com\nIP.2 = 10.0.0.1\nemail.3 = admin@example.com\n"));
+        assert!(text.contains("URI.4 = spiffe://example.org/ns/prod/sa/web\n"));
+        assert!(text.contains("otherName.5 = 1.3.6.1.4.1.311.20.2.3;UTF8:user@corp.example.com\n"));
+        assert!(text.contains("RID.6 = 1.3.6.1.4.1.99999.1\n"));
+        assert!(text.contains("dirName.7 = dir_sect_7\n\n[dir_sect_7]\nC = DE\nO = Test Inc.\nCN = Service\n"));
+    }
+
+    #[test]
+    fn test_generate_config_field_subject_order() {
+        let san = Vec::new();
+        let mut config = test_config(&san);
+        config.organizational_unit = Some("IT");
+        config.postal_code = Some("48143");
+        config.email = Some("admin@example.com");
+
+        let text = config.generate_config().unwrap();
+        assert!(text.contains("[req_distinguished_name]\nC = DE\nST = Nordrhein-Westfalen\nL = Muenster\npostalCode = 48143\nO = Test Inc.\nOU = IT\nCN = test.example.com\nemailAddress = admin@example.com\n"));
+    }
+
+    #[test]
+    fn test_generate_config_extended_subject_attributes() {
+        let san = Vec::new();
+        let mut config = test_config(&san);
+        config.domain_component = Some("example.com");
+        config.organization_identifier = Some("VATDE-123456789");
+        config.business_category = Some("Private Organization");
+        config.title = Some("Head of IT");
+        config.surname = Some("Mustermann");
+        config.given_name = Some("Erika");
+        config.pseudonym = Some("em");
+        config.serial_number = Some("HRB 12345");
+
+        let text = config.generate_config().unwrap();
+        assert!(text.contains("[req_distinguished_name]\nDC = com\n1.DC = example\nC = DE\n"));
+        assert!(text.contains("O = Test Inc.\norganizationIdentifier = VATDE-123456789\nbusinessCategory = Private Organization\ntitle = Head of IT\nSN = Mustermann\nGN = Erika\npseudonym = em\nserialNumber = HRB 12345\nCN = test.example.com\n"));
+
+        config.organization_identifier = Some("DE123456789");
+        assert!(config.generate_config().is_err());
+
+        config.organization_identifier = None;
+        config.domain_component = Some("exa mple.com");
+        assert!(config.generate_config().is_err());
+    }
+
+    #[test]
+    fn test_generate_config_ev_request() {
+        let san = vec![SanEntry::detect("test.example.com")];
+        let mut config = test_config(&san);
+        config.request_profile = "ev";
+        config.business_category = Some("Private Organization");
+        config.serial_number = Some("HRB 12345");
+        config.jurisdiction_country = Some("DE");
+        config.jurisdiction_state = Some("Nordrhein-Westfalen");
+        config.jurisdiction_locality = Some("Münster");
+
+        let text = config.generate_config().unwrap();
+        assert!(text.contains("businessCategory = Private Organization\n5.1.3.6.1.4.1.311.60.2.1.3 = DE\n6.1.3.6.1.4.1.311.60.2.1.2 = Nordrhein-Westfalen\n7.1.3.6.1.4.1.311.60.2.1.1 = Muenster\nserialNumber = HRB 12345\n"), "{}", text);
+
+        config.business_category = Some("Startup");
+        assert!(config.generate_config().is_err());
+        config.business_category = Some("Private Organization");
+
+        config.jurisdiction_state = None;
+        assert!(config.generate_config().is_err());
+        config.jurisdiction_locality = None;
+        assert!(config.generate_config().is_ok());
+
+        config.serial_number = None;
+        assert!(config.generate_config().is_err());
+        config.serial_number = Some("HRB 12345");
+
+        let wildcard = vec![SanEntry::detect("*.example.com")];
+        config.san = &wildcard;
+        assert!(config.generate_config().is_err());
+
+        let ip = vec![SanEntry::detect("10.0.0.1")];
+        config.san = &ip;
+        assert!(config.generate_config().is_err());
+    }
+
+    #[test]
+    fn test_subject_upper_bounds() {
+        let san = Vec::new();
+        let long_cn = format!("{}.example.com", "a".repeat(60));
+        let long_org = "O".repeat(65);
+        let mut config = test_config(&san);
+        config.common_name = &long_cn;
+        config.organization = &long_org;
+        config.serial_number = Some("HRB_12345");
+
+        // All violations are reported at once
+        let err = validation::ensure_valid(&config).unwrap_err().to_string();
+        let violations: Vec<&str> = err.lines().collect();
+        assert_eq!(violations.len(), 3);
+        assert!(violations[0].starts_with("O is 65 characters long, at most 64"));
+        assert!(violations[1].starts_with("serialNumber may only contain PrintableString"));
+        assert!(violations[2].contains("(ub-common-name)"));
+        assert_eq!(config.generate_config().unwrap_err().to_string(), err);
+
+        config.common_name = "test.example.com";
+        config.organization = "Test Inc.";
+        config.serial_number = Some("HRB 12345");
+        assert!(validation::ensure_valid(&config).is_ok());
+
+        // Bounds count characters, not bytes
+        let umlauts = "ü".repeat(64);
+        config.string_encoding = "utf8";
+        config.organization = &umlauts;
+        assert!(validation::ensure_valid(&config).is_ok());
+    }
+
+    #[test]
+    fn test_subject_attribute_aliases() {
+        assert_eq!(canonical_attribute("commonName"), "CN");
+        assert_eq!(canonical_attribute("2.5.4.3"), "CN");
+        assert_eq!(canonical_attribute("jurisdictionCountryName"), JURISDICTION_COUNTRY_OID);
+        assert_eq!(canonical_attribute("1.2.3.4"), "1.2.3.4");
+
+        // Long names and OIDs get the same checks as the short names
+        let san = Vec::new();
+        let long_cn = format!("{}.example.com", "a".repeat(60));
+        let long_org = "O".repeat(65);
+        let subject = vec![
+            SubjectEntry::new("countryName", "DEU"),
+            SubjectEntry::new("2.5.4.10", long_org.as_str()),
+            SubjectEntry::new("jurisdictionC", "DEU"),
+            SubjectEntry::new("commonName", ""),
+        ];
+        let mut config = test_config(&san);
+        config.string_encoding = "utf8";
+        config.common_name = &long_cn;
+        config.custom_subject = Some(&subject);
+
+        let err = validation::ensure_valid(&config).unwrap_err().to_string();
+        let violations: Vec<&str> = err.lines().collect();
+        assert_eq!(violations.len(), 4);
+        assert_eq!(violations[0], "Country code must be exactly 2 letters");
+        assert!(violations[1].starts_with("O is 65 characters long, at most 64"));
+        assert_eq!(violations[2], "Country code must be exactly 2 letters");
+        assert!(violations[3].starts_with("CN is 72 characters long, at most 64"));
+
+        assert!(check_subject_entry(&SubjectEntry::new("jurisdictionCountryName", "DEU")).unwrap_err().contains("ub-country-name-alpha-length"));
+        assert!(check_subject_entry(&SubjectEntry::new("2.5.4.5", "HRB_12345")).unwrap_err().contains("PrintableString"));
+
+        // The written config uses the short names
+        let subject = vec![SubjectEntry::new("countryName", "DE"), SubjectEntry::new("2.5.4.3", "")];
+        config.common_name = "test.example.com";
+        config.custom_subject = Some(&subject);
+        let output = config.generate_config().unwrap();
+        assert!(output.contains("C = DE\n"));
+        assert!(output.contains("CN = test.example.com\n"));
+    }
+
+    #[test]
+    fn test_is_valid_organization_identifier() {
+        assert!(is_valid_organization_identifier("VATDE-123456789"));
+        assert!(is_valid_organization_identifier("NTRDE-HRB12345"));
+        assert!(is_valid_organization_identifier("LEIXG-529900T8BM49AURSDO55"));
+        assert!(is_valid_organization_identifier("AB:DE-12345"));
+        assert!(!is_valid_organization_identifier("VATDE123456789"));
+        assert!(!is_valid_organization_identifier("vatde-123"));
+        assert!(!is_valid_organization_identifier("VATDE-"));
+    }
+
+    #[test]
+    fn test_generate_config_custom_subject() {
+        let san = Vec::new();
+        let subject = vec![
+            SubjectEntry::new("DC", "com"),
+            SubjectEntry::new("DC", "example"),
+            SubjectEntry::new("O", "Test Inc."),
+            SubjectEntry::new("OU", "Engineering"),
+            SubjectEntry { multi_valued: true, ..SubjectEntry::new("OU", "Operations") },
+            SubjectEntry::new("CN", "ignored, taken from the field"),
+            SubjectEntry { multi_valued: true, ..SubjectEntry::new("UID", "jdoe") },
+            SubjectEntry::new("1.3.6.1.4.1.311.60.2.1.3", "DE"),
+        ];
+        let mut config = test_config(&san);
+        config.custom_subject = Some(&subject);
+
+        let text = config.generate_config().unwrap();
+        assert!(text.contains("[req_distinguished_name]\nDC = com\n1.DC = example\nO = Test Inc.\nOU = Engineering\n4.+OU = Operations\nCN = test.example.com\n6.+UID = jdoe\n7.1.3.6.1.4.1.311.60.2.1.3 = DE\n"));
+        assert!(!text.contains("C = DE\n"));
+
+        let without_cn = vec![SubjectEntry::new("O", "Test Inc.")];
+        config.custom_subject = Some(&without_cn);
+        assert!(config.generate_config().is_err());
+
+        let joined_first = vec![SubjectEntry { multi_valued: true, ..SubjectEntry::new("CN", "") }];
+        config.custom_subject = Some(&joined_first);
+        assert!(config.generate_config().is_err());
+    }
+
+    #[test]
+    fn test_generate_config_string_encoding() {
+        let san = Vec::new();
+        let mut config = test_config(&san);
+        let text = config.generate_config().unwrap();
+        assert!(text.contains("L = Muenster\n"));
+        assert!(!text.contains("string_mask"));
+
+        config.string_encoding = "printable";
+        let text = config.generate_config().unwrap();
+        assert!(text.contains("string_mask = MASK:0x2002\nutf8 = yes\n"));
+        assert!(text.contains("L = Münster\n"));
+
+        config.string_encoding = "utf8";
+        let text = config.generate_config().unwrap();
+        assert!(text.contains("string_mask = utf8only\nutf8 = yes\n"));
+        assert!(text.contains("L = Münster\n"));
+
+        config.string_encoding = "latin1";
+        assert!(config.generate_config().is_err());
+    }
+
+    #[test]
+    fn test_is_printable_string() {
+        assert!(is_printable_string("Test Inc. (Europe) - 1/2"));
+        assert!(!is_printable_string("Münster"));
+        assert!(!is_printable_string("a&b"));
+        assert!(!is_printable_string("admin@example.com"));
+    }
+
+    #[test]
+    fn test_generate_config_custom_extensions() {
+        let san = Vec::new();
+        let mut extensions = vec![
+            CustomExtension { oid: "1.3.6.1.4.1.99999.1".into(), critical: true, format: ExtensionValueFormat::Asn1, value: "UTF8String:appliance-42".into() },
+            CustomExtension { oid: "1.3.6.1.4.1.99999.2".into(), critical: false, format: ExtensionValueFormat::DerHex, value: "0c 03 61:62:63".into() },
+        ];
+        let mut config = test_config(&san);
+        config.custom_extensions = &extensions;
+
+        let text = config.generate_config().unwrap();
+        assert!(text.contains("req_extensions = v3_req\n"));
+        assert!(text.contains("[v3_req]\n1.3.6.1.4.1.99999.1 = critical, ASN1:UTF8String:appliance-42\n1.3.6.1.4.1.99999.2 = DER:0C:03:61:62:63\n"));
+
+        extensions[1].value = "0c:0".into();
+        let mut config = test_config(&san);
+        config.custom_extensions = &extensions;
+        assert!(config.generate_config().is_err());
+
+        extensions[1] = CustomExtension { oid: "2.5.29.17".into(), ..extensions[0].clone() };
+        assert!(extensions[1].validate().is_err());
+
+        extensions[1] = extensions[0].clone();
+        let mut config = test_config(&san);
+        config.custom_extensions = &extensions;
+        assert!(config.generate_config().is_err());
+    }
+
+    #[test]
+    fn test_generate_config_ocsp_must_staple() {
+        let san = Vec::new();
+        let mut config = test_config(&san);
+        assert!(!config.generate_config().unwrap().contains("tlsfeature"));
+
+        config.ocsp_must_staple = true;
+        let text = config.generate_config().unwrap();
+        assert!(text.contains("req_extensions = v3_req\n"));
+        assert!(text.contains("[v3_req]\ntlsfeature = status_request\n"));
+    }
+
+    #[test]
+    fn test_generate_config_adcs_template() {
+        let san = vec![SanEntry::detect("test.example.com")];
+        let mut config = test_config(&san);
+        config.adcs_template = Some("WebServer");
+        let text = config.generate_config().unwrap();
+        assert!(text.contains("1.3.6.1.4.1.311.20.2 = ASN1:BMPString:WebServer\n"));
+        assert!(!text.contains("[adcs_template]"));
+
+        config.adcs_template = Some("1.3.6.1.4.1.311.21.8.1.2.3");
+        config.adcs_template_major_version = "100";
+        config.adcs_template_minor_version = "4";
+        let text = config.generate_config().unwrap();
+        assert!(text.contains("1.3.6.1.4.1.311.21.7 = ASN1:SEQUENCE:adcs_template\n"));
+        assert!(text.ends_with("\n[adcs_template]\ntemplateID = OID:1.3.6.1.4.1.311.21.8.1.2.3\nmajorVersion = INTEGER:100\nminorVersion = INTEGER:4\n"));
+
+        config.adcs_template_major_version = "";
+        assert!(config.generate_config().is_err());
+    }
+
+    #[test]
+    fn test_generate_config_attributes() {
+        let san = Vec::new();
+        let mut config = test_config(&san);
+        assert!(!config.generate_config().unwrap().contains("attributes"));
+
+        config.challenge_password = Some("enrol-secret");
+        config.unstructured_name = Some("Test Inc. Appliance");
+        let text = config.generate_config().unwrap();
+        assert!(text.contains("attributes = req_attributes\n"));
+        assert!(text.contains("challengePassword = $ENV::CSR_GENERATOR_CHALLENGE_PASSWORD\nunstructuredName = Test Inc. Appliance\n"));
+        assert!(!text.contains("enrol-secret"));
+
+        let long = "x".repeat(UB_PKCS9_STRING + 1);
+        config.challenge_password = Some(&long);
+        assert!(config.generate_config().is_err());
+    }
+
+    #[test]
+    fn test_san_detect_and_validate() {
+        assert_eq!(SanType::detect("2001:db8::1"), SanType::Ip);
+        assert_eq!(SanType::detect("urn:uuid:123"), SanType::Uri);
+        assert_eq!(SanType::detect("*.example.com"), SanType::Dns);
+        assert!(SanEntry::new(SanType::Ip, "example.com").validate().is_err());
+        assert!(SanEntry::new(SanType::Email, "@example.com").validate().is_err());
+        assert!(SanEntry::new(SanType::Uri, "not a uri").validate().is_err());
+        assert!(SanEntry::new(SanType::Rid, "serverAuth").validate().is_err());
+        assert!(SanEntry::new(SanType::DirName, "CN=Service, O=Example").validate().is_ok());
+        assert!(SanEntry::new(SanType::DirName, "Service").validate().is_err());
+
+        // RFC 4514 escapes keep separators inside values
+        let attributes = |value: &str| parse_dir_name(value).map(|parsed| parsed.into_iter().map(|(a, v)| format!("{}={}", a, v)).collect::<Vec<_>>());
+        assert_eq!(attributes(r"CN=Service, O=Example\, Inc."), Some(vec!["CN=Service".to_string(), "O=Example, Inc.".to_string()]));
+        assert_eq!(attributes(r"O=Example\2C Inc.,OU=M\C3\BCnster"), Some(vec!["O=Example, Inc.".to_string(), "OU=Münster".to_string()]));
+        assert_eq!(attributes(r"/CN=a\/b/O=\\ Example\ "), Some(vec!["CN=a/b".to_string(), r"O=\ Example ".to_string()]));
+        assert_eq!(attributes(r"CN=dangling\"), None);
+        assert_eq!(attributes(r"CN=bad\C3\28"), None);
+
+        // rfc822Name and URI are IA5Strings, only the email domain can be converted to A-labels
+        assert!(SanEntry::new(SanType::Email, "jürgen@example.com").validate().unwrap_err().contains("ASCII"));
+        assert!(SanEntry::new(SanType::Uri, "https://bücher.de/").validate().unwrap_err().contains("percent-encode"));
+        assert!(SanEntry::new(SanType::Upn, "jürgen@corp.example.com").validate().is_ok());
+        let san = vec![SanEntry::new(SanType::Email, "info@bücher.de")];
+        assert!(san[0].validate().is_ok());
+        assert!(test_config(&san).generate_config().unwrap().contains("email.1 = info@xn--bcher-kva.de\n"));
+    }
+
+    #[test]
+    fn test_idna_conversion() {
+        assert_eq!(dns_to_ascii("bücher.de").unwrap(), "xn--bcher-kva.de");
+        assert_eq!(dns_to_ascii("*.Bücher.de").unwrap(), "*.xn--bcher-kva.de");
+        assert_eq!(dns_to_ascii("www.example.com").unwrap(), "www.example.com");
+        assert!(dns_to_ascii("bü cher.de").is_err());
+        assert_eq!(dns_to_unicode("xn--bcher-kva.de").as_deref(), Some("bücher.de"));
+        assert_eq!(dns_to_unicode("*.xn--bcher-kva.de").as_deref(), Some("*.bücher.de"));
+        assert_eq!(dns_to_unicode("www.example.com"), None);
+        assert!(SanEntry::detect("bücher.de").validate().is_ok());
+
+        // CN and SAN are written as A-labels, a CN that is no SAN stays as typed
+        let san = vec![SanEntry::detect("bücher.de"), SanEntry::detect("www.bücher.de")];
+        let mut config = test_config(&san);
+        config.common_name = "bücher.de";
+        config.string_encoding = "utf8";
+        let text = config.generate_config().unwrap();
+        assert!(text.contains("CN = xn--bcher-kva.de\n"));
+        assert!(text.contains("DNS.1 = xn--bcher-kva.de\nDNS.2 = www.xn--bcher-kva.de\n"));
+
+        config.common_name = "Bücher GmbH";
+        assert!(config.generate_config().unwrap().contains("CN = Bücher GmbH\n"));
+    }
+
+    #[test]
+    fn test_dns_name_validation() {
+        for valid in ["example.com", "*.example.com", "a-b.example.co.uk", "xn--bcher-kva.de", "intranet", "x1.example.com"] {
+            assert_eq!(validate_dns_name(valid), Ok(()), "{}", valid);
+        }
+
+        let problem = |name: &str| validate_dns_name(name).unwrap_err();
+        assert!(problem("https://www.example.com/login").contains("looks like a URL"));
+        assert!(problem("www.example.com:443").contains("remove the port :443"));
+        assert!(problem("admin@example.com").contains("Email type"));
+        assert!(problem("10.0.0.1").contains("IP type"));
+        assert!(problem("example.com.").contains("trailing dot"));
+        assert!(problem("www..example.com").contains("empty label"));
+        assert!(problem("my_host.example.com").contains("letters, digits and hyphens"));
+        assert!(problem("-www.example.com").contains("hyphen"));
+        assert!(problem("ab--c.example.com").contains("third and fourth"));
+        assert!(problem(&format!("{}.com", "a".repeat(64))).contains("longer than 63"));
+        assert!(problem(&["abcdefghi"; 26].join(".")).contains("longer than 253"));
+        assert!(problem("example.123").contains("numeric"));
+
+        // Wildcards only as the whole leftmost label, and not for a whole TLD
+        assert!(problem("www.*.example.com").contains("only allowed as the leftmost"));
+        assert!(problem("w*.example.com").contains("whole leftmost label"));
+        assert!(problem("*.com").contains("at least two labels"));
+        assert!(problem("*").contains("at least two labels"));
+
+        // A pasted web address stays a DNS name so the mistake is reported
+        assert_eq!(SanType::detect("https://www.example.com/"), SanType::Dns);
+        assert_eq!(SanType::detect("spiffe://example.org/service"), SanType::Uri);
+    }
+
+    #[test]
+    fn test_is_valid_oid() {
+        assert!(is_valid_oid("1.3.6.1.5.5.7.3.1"));
+        assert!(is_valid_oid("2.5"));
+        assert!(!is_valid_oid("1"));
+        assert!(!is_valid_oid("3.1"));
+        assert!(!is_valid_oid("1..2"));
+        assert!(!is_valid_oid("serverAuth"));
+    }
+
+    #[test]
+    fn test_generate_config_rejects_unknown_curve() {
+        let san = Vec::new();
+        let mut config = test_config(&san);
+        config.key_algorithm = "ec";
+        config.ec_curve = "brainpoolP256r1";
+        assert!(config.generate_config().is_err());
+    }
+
+    #[test]
+    fn test_file_name_wildcard() {
+        let san = Vec::new();
+        let mut config = test_config(&san);
+        config.common_name = "*.example.com";
+        assert_eq!(config.file_name(), "wildcard.example.com");
+    }
+
     #[test]
     fn test_sanitize_german_characters() {
         assert_eq!(sanitize("Müller"), "Mueller");
@@ -436,4 +2147,132 @@ mod tests {
         // sanitize_for_cert_field preserves spaces
         assert_eq!(sanitize_for_cert_field(input), "Mueller and Soehne GmbH");
     }
+
+    #[test]
+    fn test_san_normalization() {
+        assert_eq!(SanEntry::detect("WWW.Example.COM.").normalized().value, "www.example.com");
+        assert_eq!(SanEntry::detect("2001:DB8:0:0:0:0:0:1").normalized().value, "2001:db8::1");
+        assert_eq!(SanEntry::new(SanType::Email, "John.Doe@Example.COM").normalized().value, "John.Doe@example.com");
+        assert_eq!(SanEntry::new(SanType::Uri, " https://Example.com/A ").normalized().value, "https://Example.com/A");
+
+        let sans = vec![
+            SanEntry::detect("example.com"),
+            SanEntry::detect("EXAMPLE.com."),
+            SanEntry::detect("bücher.de"),
+            SanEntry::detect("xn--bcher-kva.de"),
+            SanEntry::detect("*.example.com"),
+            SanEntry::detect("www.example.com"),
+            SanEntry::detect("::1"),
+            SanEntry::detect("0:0::1"),
+        ];
+        let normalized: Vec<String> = normalize_sans(&sans).iter().map(ToString::to_string).collect();
+        assert_eq!(normalized, ["DNS:example.com", "DNS:bücher.de", "DNS:*.example.com", "DNS:www.example.com", "IP:::1"]);
+
+        assert_eq!(san_redundancies(&sans), [
+            (1, SanRedundancy::Duplicate(0)),
+            (3, SanRedundancy::Duplicate(2)),
+            (5, SanRedundancy::CoveredBy(4)),
+            (7, SanRedundancy::Duplicate(6)),
+        ]);
+
+        // A wildcard covers exactly one label
+        let wildcard = SanEntry::detect("*.example.com");
+        assert!(wildcard.covers(&SanEntry::detect("WWW.example.com")));
+        assert!(!wildcard.covers(&SanEntry::detect("example.com")));
+        assert!(!wildcard.covers(&SanEntry::detect("a.b.example.com")));
+        assert!(!wildcard.covers(&wildcard));
+
+        // Both backends write the normalized list
+        let config = test_config(&sans);
+        assert_eq!(config.san_entries().len(), 5);
+    }
+
+    #[test]
+    fn test_cn_san_sync() {
+        let mut sans = Vec::new();
+        sync_cn_san(&mut sans, "", "a");
+        sync_cn_san(&mut sans, "a", "ab.example.com");
+        assert_eq!(sans, [SanEntry::new(SanType::Dns, "ab.example.com")]);
+
+        // An entry added by hand is never overwritten
+        let mut sans = vec![SanEntry::detect("other.example.com")];
+        sync_cn_san(&mut sans, "", "www.example.com");
+        assert_eq!(sans, [SanEntry::detect("www.example.com"), SanEntry::detect("other.example.com")]);
+        sync_cn_san(&mut sans, "www.example.com", "");
+        assert_eq!(sans, [SanEntry::detect("other.example.com")]);
+
+        // Turning the sync on moves an already listed CN to the front
+        let mut sans = vec![SanEntry::detect("a.example.com"), SanEntry::detect("WWW.example.com")];
+        mirror_cn_san(&mut sans, "www.example.com");
+        assert_eq!(sans, [SanEntry::detect("www.example.com"), SanEntry::detect("a.example.com")]);
+    }
+
+    /// A request that uses every section the generator writes
+    fn full_config<'a>(san: &'a Vec<SanEntry>, key_usage: &'a Vec<String>, extended_key_usage: &'a Vec<String>, extensions: &'a Vec<CustomExtension>) -> CertConfig<'a> {
+        let mut config = test_config(san);
+        config.organizational_unit = Some("IT");
+        config.email = Some("admin@example.com");
+        config.string_encoding = "printable";
+        config.key_usage = key_usage;
+        config.extended_key_usage = extended_key_usage;
+        config.custom_extensions = extensions;
+        config.ocsp_must_staple = true;
+        config.adcs_template = Some("1.3.6.1.4.1.311.21.8.1.2");
+        config.adcs_template_major_version = "100";
+        config.adcs_template_minor_version = "4";
+        config.signature_scheme = "pss";
+        config.challenge_password = Some("secret");
+        config.unstructured_name = Some("Appliance 42");
+        config
+    }
+
+    #[test]
+    fn test_generate_config_golden() {
+        let san = vec![SanEntry::detect("test.example.com")];
+        assert_eq!(test_config(&san).generate_config().unwrap(), include_str!("../tests/golden/minimal.cnf"));
+
+        let san = vec![
+            SanEntry::detect("test.example.com"),
+            SanEntry::detect("bücher.de"),
+            SanEntry::detect("2001:db8::1"),
+            SanEntry::new(SanType::Upn, "user@corp.example.com"),
+            SanEntry::new(SanType::DirName, "CN=Service, O=Example"),
+        ];
+        let key_usage = vec!["digitalSignature".to_string()];
+        let extended_key_usage = vec!["serverAuth".to_string(), "clientAuth".to_string()];
+        let extensions = vec![CustomExtension {
+            oid: "1.3.6.1.4.1.99999.1".to_string(),
+            critical: true,
+            format: ExtensionValueFormat::Asn1,
+            value: "UTF8String:appliance-42".to_string(),
+        }];
+        let config = full_config(&san, &key_usage, &extended_key_usage, &extensions);
+        assert_eq!(config.generate_config().unwrap(), include_str!("../tests/golden/full.cnf"));
+    }
+
+    #[test]
+    fn test_generate_config_escapes_values() {
+        let san = vec![
+            SanEntry::detect("test.example.com"),
+            SanEntry::new(SanType::Uri, "https://example.com/$HOME#top"),
+        ];
+        let mut config = test_config(&san);
+        config.string_encoding = "utf8";
+        config.organization = "O'Reilly \"Books\" $5 #1";
+        config.locality = r"C:\Users";
+        config.organizational_unit = Some("@admins, @ops");
+        config.unstructured_name = Some("x ${ENV::HOME}");
+        let text = config.generate_config().unwrap();
+        assert_eq!(text, include_str!("../tests/golden/escaping.cnf"));
+
+        // The values read back exactly as entered, nothing is expanded
+        let conf = OpensslConf::parse(&text).unwrap();
+        let no_env = |_: &str| None;
+        let value = |section: &str, key: &str| conf.expand(section, conf.get(section, key).unwrap(), &no_env).unwrap();
+        assert_eq!(value("req_distinguished_name", "O"), "O'Reilly \"Books\" $5 #1");
+        assert_eq!(value("req_distinguished_name", "L"), r"C:\Users");
+        assert_eq!(value("req_distinguished_name", "OU"), "@admins, @ops");
+        assert_eq!(value("req_attributes", "unstructuredName"), "x ${ENV::HOME}");
+        assert_eq!(value("alt_names", "URI.2"), "https://example.com/$HOME#top");
+    }
 }
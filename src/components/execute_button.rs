@@ -2,13 +2,16 @@ use eframe::egui;
 use std::fs::File;
 use std::io::Write;
 use crate::CertGenApp;
-#[cfg(feature = "openssl-native")]
-use crate::cert_config::CertConfig;
+use crate::cert_config::{CHALLENGE_PASSWORD_ENV, CertConfig};
 use crate::openssl_cli::execute_openssl_command;
 #[cfg(feature = "openssl-native")]
-use crate::openssl_native::generate_cert_request;
+use crate::openssl_native::{generate_cert_request, request_facts};
+use crate::lint;
 use crate::components::generate_and_save;
 
+/// Environment variable used to pass key passphrases to `openssl req -passin`/`-passout`
+const KEY_PASSPHRASE_ENV: &str = "CSR_GENERATOR_KEY_PASSPHRASE";
+
 pub fn render(ui: &mut egui::Ui, app: &mut CertGenApp) {
     if !app.config_output.is_empty() {
         let button = egui::Button::new(
@@ -44,35 +47,30 @@ fn execute(app: &mut CertGenApp) {
     if use_native {
         #[cfg(feature = "openssl-native")]
         {
-            let file_common_name = if app.common_name.starts_with("*.") {
-                app.common_name.replacen("*.", "wildcard.", 1)
-            } else {
-                app.common_name.clone()
-            };
-
             // Build config struct
             let config = CertConfig::from(&*app);
+            let file_common_name = config.file_name();
+            let openssl_for_zip = config.req_command(&format!("{}.cnf", file_common_name));
             // Generate cert using OpenSSL library
             match generate_cert_request(&config) {
                 Ok(cert) => {
-                    app.key_content = cert.key_pem;
+                    app.key_content = cert.key_pem.unwrap_or_default();
                     app.csr_content = cert.csr_pem;
                     app.openssl_output.push_str("Certificate request generated successfully!\n");
+                    lint_request(app);
 
                     // Auto-save
-                    if !app.key_content.is_empty() && !app.csr_content.is_empty() {
+                    if app.has_request_files() {
                         match generate_and_save(
                             &app.config_output,
                             &file_common_name,
-                            &app.key_content,
+                            app.new_key_content(),
                             &app.csr_content,
+                            &openssl_for_zip,
+                            app.encrypts_new_key(),
                         ) {
                             Ok(_) => {
                                 app.openssl_output.push_str("Auto saved zip to downloads folder\n");
-                                let openssl_for_zip = format!(
-                                    "openssl req -new -out {}.csr -config {}.cnf",
-                                    file_common_name, file_common_name
-                                );
                                 app.openssl_output.push_str(&format!("Use this command to recreate the csr: {}\n", openssl_for_zip));
                             }
                             Err(err) => {
@@ -116,29 +114,43 @@ fn execute(app: &mut CertGenApp) {
             }
         }
 
-        let file_common_name = if app.common_name.starts_with("*.") {
-            app.common_name.replacen("*.", "wildcard.", 1)
-        } else {
-            app.common_name.clone()
-        };
-
-        let openssl_command = format!("openssl req -new -out {}.csr -config {}", file_common_name, temp_file);
-        log::info!("Executing: {}", openssl_command);
+        let config = CertConfig::from(&*app);
+        let file_common_name = config.file_name();
+        let mut openssl_args = config.req_args(temp_file);
+        let openssl_for_zip = config.req_command(&format!("{}.cnf", file_common_name));
+        let reuses_key = config.existing_key.is_some();
+        log::info!("Executing: {}", config.req_command(temp_file));
+
+        // Passphrases and the challenge password are handed over through the environment
+        let mut envs = Vec::new();
+        if let (true, Some(passphrase)) = (reuses_key, config.existing_key_passphrase) {
+            openssl_args.extend(["-passin".to_string(), format!("env:{}", KEY_PASSPHRASE_ENV)]);
+            envs.push((KEY_PASSPHRASE_ENV, passphrase));
+        }
+        if let Some(passphrase) = config.key_passphrase {
+            openssl_args.extend(["-passout".to_string(), format!("env:{}", KEY_PASSPHRASE_ENV)]);
+            envs.push((KEY_PASSPHRASE_ENV, passphrase));
+        }
+        if let Some(password) = config.challenge_password {
+            envs.push((CHALLENGE_PASSWORD_ENV, password));
+        }
 
-        match execute_openssl_command(&openssl_command) {
+        match execute_openssl_command(&openssl_args, &envs) {
             Ok((stdout, stderr)) => {
                 app.openssl_output.push_str(&stdout);
                 app.openssl_output.push_str(&stderr);
 
-                // Read the key file
-                match std::fs::read_to_string(format!("{}.key", file_common_name)) {
-                    Ok(content) => {
-                        app.key_content = content;
-                        if let Err(err) = std::fs::remove_file(format!("{}.key", file_common_name)) {
-                            log::error!("Error removing key file: {}", err);
+                // Read the key file, an existing key stays where it is
+                if !reuses_key {
+                    match std::fs::read_to_string(format!("{}.key", file_common_name)) {
+                        Ok(content) => {
+                            app.key_content = content;
+                            if let Err(err) = std::fs::remove_file(format!("{}.key", file_common_name)) {
+                                log::error!("Error removing key file: {}", err);
+                            }
                         }
+                        Err(_) => app.key_content = "Error reading key file".to_string(),
                     }
-                    Err(_) => app.key_content = "Error reading key file".to_string(),
                 }
 
                 // Read the CSR file
@@ -148,24 +160,23 @@ fn execute(app: &mut CertGenApp) {
                         if let Err(err) = std::fs::remove_file(format!("{}.csr", file_common_name)) {
                             log::error!("Error removing csr file: {}", err);
                         }
+                        lint_request(app);
                     }
                     Err(_) => app.csr_content = "Error reading CSR file".to_string(),
                 }
 
                 // Auto-save if both files were read successfully
-                if !app.key_content.is_empty() && !app.csr_content.is_empty() {
+                if app.has_request_files() {
                     match generate_and_save(
                         &app.config_output,
                         &file_common_name,
-                        &app.key_content,
+                        app.new_key_content(),
                         &app.csr_content,
+                        &openssl_for_zip,
+                        app.encrypts_new_key(),
                     ) {
                         Ok(_) => {
                             app.openssl_output.push_str("Auto saved zip to downloads folder\n");
-                            let openssl_for_zip = format!(
-                                "openssl req -new -out {}.csr -config {}.cnf",
-                                file_common_name, file_common_name
-                            );
                             app.openssl_output.push_str(&format!("Use this command to recreate the csr: {}\n", openssl_for_zip));
                         }
                         Err(err) => {
@@ -190,3 +201,23 @@ fn execute(app: &mut CertGenApp) {
 
     app.is_executing = false;
 }
+
+/// Lints the request as the backend wrote it, which also covers an existing key the form knows nothing about
+#[cfg(feature = "openssl-native")]
+fn lint_request(app: &mut CertGenApp) {
+    match request_facts(&app.csr_content) {
+        Ok(facts) => {
+            app.openssl_output.push_str("Checking the generated request:\n");
+            app.openssl_output.push_str(&lint::report(&lint::lint(&facts, &app.lint_policy), &app.lint_policy));
+        }
+        Err(err) => log::warn!("Could not read the generated request for linting: {}", err),
+    }
+}
+
+/// Without the native backend the request cannot be read back, so the form values are linted instead
+#[cfg(not(feature = "openssl-native"))]
+fn lint_request(app: &mut CertGenApp) {
+    let facts = lint::RequestFacts::from_config(&CertConfig::from(&*app));
+    app.openssl_output.push_str("Checking the form values (an existing key is not inspected):\n");
+    app.openssl_output.push_str(&lint::report(&lint::lint(&facts, &app.lint_policy), &app.lint_policy));
+}
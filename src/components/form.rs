@@ -1,7 +1,15 @@
 use eframe::egui;
 use crate::CertGenApp;
+use crate::cert_config::{CertConfig, CustomExtension, EC_CURVES, EV_BUSINESS_CATEGORIES, EXTENDED_KEY_USAGES, ExtensionValueFormat, KEY_USAGES, STRING_ENCODINGS, SUBJECT_ATTRIBUTES, SanEntry, SanType, SubjectEntry, canonical_attribute, dns_to_ascii, dns_to_unicode, is_eddsa, is_valid_oid, mirror_cn_san, normalize_sans, sync_cn_san};
+use crate::countries;
+use crate::lint::LINT_POLICIES;
+use crate::passphrase::{self, Strength};
+use crate::public_suffix;
+use crate::validation::{self, FieldId, Issue, Severity};
+
+/// `issues` are the validation results of this frame, computed once by the caller
+pub fn render(ui: &mut egui::Ui, app: &mut CertGenApp, issues: &[Issue]) {
 
-pub fn render(ui: &mut egui::Ui, app: &mut CertGenApp) {
     egui::Frame::group(ui.style())
         .inner_margin(10.0)
         .show(ui, |ui| {
@@ -9,7 +17,20 @@ pub fn render(ui: &mut egui::Ui, app: &mut CertGenApp) {
 
             // Advanced mode toggle in top right
             ui.horizontal(|ui| {
-                ui.label("");
+                // Which CA the request lint checks against
+                ui.label("Target CA:");
+                let selected = LINT_POLICIES.iter()
+                    .find(|(mode, _)| *mode == app.lint_policy)
+                    .map_or(app.lint_policy.as_str(), |(_, label)| *label);
+                egui::ComboBox::from_id_salt("lint_policy")
+                    .selected_text(selected)
+                    .show_ui(ui, |ui| {
+                        for (mode, label) in LINT_POLICIES {
+                            ui.selectable_value(&mut app.lint_policy, mode.to_string(), label);
+                        }
+                    })
+                    .response
+                    .on_hover_text("Public CAs follow the CA/Browser Forum Baseline Requirements, the lint results are shown in the output");
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                     ui.checkbox(&mut app.advanced_mode, "Advanced");
                     #[cfg(feature = "openssl-native")]
@@ -22,37 +43,42 @@ pub fn render(ui: &mut egui::Ui, app: &mut CertGenApp) {
             ui.separator();
             ui.add_space(5.0);
 
-            // Country Code
-            ui.horizontal(|ui| {
-                ui.label("Country Code (2 letters):");
-                ui.add(egui::TextEdit::singleline(&mut app.country)
-                    .hint_text("DE")
-                    .desired_width(200.0));
-            });
+            // The custom subject editor replaces the fixed subject fields
+            if !app.uses_custom_subject() {
+                // Country Code
+                ui.horizontal(|ui| {
+                    ui.label("Country:");
+                    let response = country_picker(ui, "country", &mut app.country, &mut app.country_search);
+                    mark(ui, response, issues, FieldId::Country);
+                });
 
-            // State/Province
-            ui.horizontal(|ui| {
-                ui.label("State/Province:");
-                ui.add(egui::TextEdit::singleline(&mut app.state)
-                    .hint_text("Nordrhein-Westfalen")
-                    .desired_width(200.0));
-            });
+                // State/Province
+                ui.horizontal(|ui| {
+                    ui.label("State/Province:");
+                    let response = ui.add(egui::TextEdit::singleline(&mut app.state)
+                        .hint_text("Nordrhein-Westfalen")
+                        .desired_width(200.0));
+                    mark(ui, response, issues, FieldId::State);
+                });
 
-            // Locality
-            ui.horizontal(|ui| {
-                ui.label("Locality (city):");
-                ui.add(egui::TextEdit::singleline(&mut app.locality)
-                    .hint_text("Münster")
-                    .desired_width(200.0));
-            });
+                // Locality
+                ui.horizontal(|ui| {
+                    ui.label("Locality (city):");
+                    let response = ui.add(egui::TextEdit::singleline(&mut app.locality)
+                        .hint_text("Münster")
+                        .desired_width(200.0));
+                    mark(ui, response, issues, FieldId::Locality);
+                });
 
-            // Organization
-            ui.horizontal(|ui| {
-                ui.label("Organization:");
-                ui.add(egui::TextEdit::singleline(&mut app.organization)
-                    .hint_text("Test Inc.")
-                    .desired_width(200.0));
-            });
+                // Organization
+                ui.horizontal(|ui| {
+                    ui.label("Organization:");
+                    let response = ui.add(egui::TextEdit::singleline(&mut app.organization)
+                        .hint_text("Test Inc.")
+                        .desired_width(200.0));
+                    mark(ui, response, issues, FieldId::Organization);
+                });
+            }
 
             // Advanced mode fields
             if app.advanced_mode {
@@ -60,59 +86,436 @@ pub fn render(ui: &mut egui::Ui, app: &mut CertGenApp) {
                 ui.label(egui::RichText::new("Advanced Options").strong());
                 ui.separator();
 
-                // Organizational Unit
+                // Request Profile
                 ui.horizontal(|ui| {
-                    ui.label("Organizational Unit (OU):");
-                    ui.add(egui::TextEdit::singleline(&mut app.organizational_unit)
-                        .hint_text("IT Department")
-                        .desired_width(200.0));
+                    ui.label("Request Profile:");
+                    let previous = app.request_profile.clone();
+                    let response = egui::ComboBox::from_id_salt("request_profile")
+                        .selected_text(match app.request_profile.as_str() {
+                            "ca" => "Sub-CA",
+                            "ev" => "Extended Validation (EV)",
+                            _ => "Server / Client",
+                        })
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut app.request_profile, "end_entity".to_string(), "Server / Client");
+                            ui.selectable_value(&mut app.request_profile, "ev".to_string(), "Extended Validation (EV)");
+                            ui.selectable_value(&mut app.request_profile, "ca".to_string(), "Sub-CA");
+                        }).response;
+                    mark(ui, response, issues, FieldId::RequestProfile);
+
+                    // A CA name is not a host name, drop the SAN that mirrored the CN
+                    if previous != app.request_profile && app.request_profile == "ca" && app.sync_cn_san {
+                        sync_cn_san(&mut app.sans, &app.common_name, "");
+                    }
                 });
 
-                // Email
+                if app.request_profile == "ca" {
+                    ui.horizontal(|ui| {
+                        ui.label("Path Length:");
+                        let response = ui.add(egui::TextEdit::singleline(&mut app.ca_path_len)
+                            .hint_text("unlimited")
+                            .desired_width(100.0));
+                        mark(ui, response, issues, FieldId::CaPathLen);
+                    });
+                }
+
+                if app.request_profile == "ev" {
+                    ev_details(ui, app, issues);
+                }
+
+                // Subject string encoding
                 ui.horizontal(|ui| {
-                    ui.label("Email Address:");
-                    ui.add(egui::TextEdit::singleline(&mut app.email)
-                        .hint_text("admin@example.com")
-                        .desired_width(200.0));
+                    ui.label("Subject Encoding:");
+                    let selected = STRING_ENCODINGS.iter()
+                        .find(|(mode, _)| *mode == app.string_encoding)
+                        .map_or(app.string_encoding.as_str(), |(_, label)| *label);
+                    let response = egui::ComboBox::from_id_salt("string_encoding")
+                        .selected_text(selected)
+                        .show_ui(ui, |ui| {
+                            for (mode, label) in STRING_ENCODINGS {
+                                ui.selectable_value(&mut app.string_encoding, mode.to_string(), label);
+                            }
+                        }).response;
+                    mark(ui, response, issues, FieldId::StringEncoding);
+                });
+
+                // Subject order, repeated attributes and multi-valued RDNs
+                ui.add_space(5.0);
+                let was_custom = app.custom_subject;
+                ui.checkbox(&mut app.custom_subject, "Custom subject (order, repeated and multi-valued RDNs)");
+                if app.custom_subject && !was_custom && app.subject.is_empty() {
+                    app.subject = CertConfig::from(&*app).field_subject();
+                }
+
+                if app.custom_subject {
+                    subject_editor(ui, app, issues);
+                } else {
+                    // Organizational Unit
+                    ui.horizontal(|ui| {
+                        ui.label("Organizational Unit (OU):");
+                        let response = ui.add(egui::TextEdit::singleline(&mut app.organizational_unit)
+                            .hint_text("IT Department")
+                            .desired_width(200.0));
+                        mark(ui, response, issues, FieldId::OrganizationalUnit);
+                    });
+
+                    // Email
+                    ui.horizontal(|ui| {
+                        ui.label("Email Address:");
+                        let response = ui.add(egui::TextEdit::singleline(&mut app.email)
+                            .hint_text("admin@example.com")
+                            .desired_width(200.0));
+                        mark(ui, response, issues, FieldId::Email);
+                    });
+
+                    // Street Address
+                    ui.horizontal(|ui| {
+                        ui.label("Street Address:");
+                        let response = ui.add(egui::TextEdit::singleline(&mut app.street_address)
+                            .hint_text("123 Main Street")
+                            .desired_width(200.0));
+                        mark(ui, response, issues, FieldId::StreetAddress);
+                    });
+
+                    // Postal Code
+                    ui.horizontal(|ui| {
+                        ui.label("Postal Code:");
+                        let response = ui.add(egui::TextEdit::singleline(&mut app.postal_code)
+                            .hint_text("12345")
+                            .desired_width(200.0));
+                        mark(ui, response, issues, FieldId::PostalCode);
+                    });
+
+                    // Further subject attributes for eIDAS and personal certificates, EV has its own section
+                    let is_ev = app.is_ev_request();
+                    for (label, value, hint, field) in [
+                        ("Organization Identifier:", &mut app.organization_identifier, "VATDE-123456789", FieldId::OrganizationIdentifier),
+                        ("Business Category:", &mut app.business_category, "Private Organization", FieldId::BusinessCategory),
+                        ("Title:", &mut app.title, "Head of IT", FieldId::Title),
+                        ("Given Name:", &mut app.given_name, "Erika", FieldId::GivenName),
+                        ("Surname:", &mut app.surname, "Mustermann", FieldId::Surname),
+                        ("Pseudonym:", &mut app.pseudonym, "erika", FieldId::Pseudonym),
+                        ("Serial Number:", &mut app.serial_number, "HRB 12345", FieldId::SerialNumber),
+                        ("Domain Components:", &mut app.domain_component, "example.com", FieldId::DomainComponent),
+                    ] {
+                        if is_ev && matches!(field, FieldId::BusinessCategory | FieldId::SerialNumber) {
+                            continue;
+                        }
+                        ui.horizontal(|ui| {
+                            ui.label(label);
+                            let response = ui.add(egui::TextEdit::singleline(value)
+                                .hint_text(hint)
+                                .desired_width(200.0));
+                            mark(ui, response, issues, field);
+                        });
+                    }
+                }
+
+                // Existing key
+                ui.horizontal(|ui| {
+                    if ui.checkbox(&mut app.use_existing_key, "Use existing private key").changed() {
+                        reset_signature_scheme(app);
+                    }
+                });
+
+                if app.use_existing_key {
+                    ui.horizontal(|ui| {
+                        ui.label("Private Key File (PEM/DER):");
+                        let response = ui.add(egui::TextEdit::singleline(&mut app.existing_key_path)
+                            .hint_text("/path/to/existing.key")
+                            .desired_width(300.0));
+                        mark(ui, response, issues, FieldId::ExistingKey);
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Key Passphrase:");
+                        ui.add(egui::TextEdit::singleline(&mut app.existing_key_passphrase)
+                            .password(true)
+                            .hint_text("only for encrypted keys")
+                            .desired_width(200.0));
+                    });
+                }
+
+                // Key parameters only matter when a new key is generated
+                if !app.use_existing_key {
+                    // Passphrase for the new key
+                    ui.horizontal(|ui| {
+                        ui.label("Key Passphrase:");
+                        let response = ui.add(egui::TextEdit::singleline(&mut app.key_passphrase)
+                            .password(true)
+                            .hint_text("leave empty for an unencrypted key")
+                            .desired_width(200.0));
+                        mark(ui, response, issues, FieldId::KeyPassphrase);
+                    });
+
+                    if !app.key_passphrase.is_empty() {
+                        ui.horizontal(|ui| {
+                            ui.label("Confirm Passphrase:");
+                            let response = ui.add(egui::TextEdit::singleline(&mut app.key_passphrase_confirm)
+                                .password(true)
+                                .desired_width(200.0));
+                            mark(ui, response, issues, FieldId::KeyPassphrase);
+                        });
+
+                        ui.horizontal(|ui| {
+                            ui.label("Strength:");
+                            let strength = passphrase::strength(&app.key_passphrase);
+                            let color = match strength {
+                                Strength::VeryWeak | Strength::Weak => egui::Color32::RED,
+                                Strength::Reasonable => egui::Color32::YELLOW,
+                                Strength::Strong | Strength::VeryStrong => egui::Color32::GREEN,
+                            };
+                            ui.add(egui::ProgressBar::new(strength.fraction())
+                                .desired_width(200.0)
+                                .fill(color)
+                                .text(strength.label()));
+                        });
+                    }
+
+                    // Key Algorithm
+                    ui.horizontal(|ui| {
+                        ui.label("Key Algorithm:");
+                        let previous = app.key_algorithm.clone();
+                        let response = egui::ComboBox::from_id_salt("key_algorithm")
+                            .selected_text(match app.key_algorithm.as_str() {
+                                "ec" => "ECDSA",
+                                "ed25519" => "Ed25519",
+                                "ed448" => "Ed448",
+                                _ => "RSA",
+                            })
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut app.key_algorithm, "rsa".to_string(), "RSA");
+                                ui.selectable_value(&mut app.key_algorithm, "ec".to_string(), "ECDSA");
+                                ui.selectable_value(&mut app.key_algorithm, "ed25519".to_string(), "Ed25519");
+                                ui.selectable_value(&mut app.key_algorithm, "ed448".to_string(), "Ed448");
+                            }).response;
+                        if app.key_algorithm != previous {
+                            reset_signature_scheme(app);
+                        }
+                        mark(ui, response, issues, FieldId::KeyAlgorithm);
+                    });
+
+                    if app.key_algorithm == "ec" {
+                        // Curve
+                        ui.horizontal(|ui| {
+                            ui.label("Curve:");
+                            let selected = EC_CURVES.iter()
+                                .find(|(curve, _)| *curve == app.ec_curve)
+                                .map_or(app.ec_curve.as_str(), |(_, nist)| *nist);
+                            egui::ComboBox::from_id_salt("ec_curve")
+                                .selected_text(selected)
+                                .show_ui(ui, |ui| {
+                                    for (curve, nist) in EC_CURVES {
+                                        ui.selectable_value(&mut app.ec_curve, curve.to_string(), format!("{} ({})", nist, curve));
+                                    }
+                                });
+                        });
+                    } else if app.key_algorithm == "rsa" {
+                        // Key Size
+                        ui.horizontal(|ui| {
+                            ui.label("Key Size:");
+                            egui::ComboBox::from_id_salt("key_size")
+                                .selected_text(&app.key_size)
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(&mut app.key_size, "2048".to_string(), "2048 bits");
+                                    ui.selectable_value(&mut app.key_size, "4096".to_string(), "4096 bits");
+                                });
+                        });
+                    }
+                }
+
+                // Hash Algorithm, EdDSA signs without a separate digest
+                ui.horizontal(|ui| {
+                    ui.label("Hash Algorithm:");
+                    ui.add_enabled_ui(app.use_existing_key || !is_eddsa(&app.key_algorithm), |ui| {
+                        egui::ComboBox::from_id_salt("hash_algo")
+                            .selected_text(&app.hash_algorithm)
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut app.hash_algorithm, "sha256".to_string(), "SHA-256");
+                                ui.selectable_value(&mut app.hash_algorithm, "sha384".to_string(), "SHA-384");
+                                ui.selectable_value(&mut app.hash_algorithm, "sha512".to_string(), "SHA-512");
+                            });
+                    }).response.on_disabled_hover_text("EdDSA keys do not use a separate hash algorithm");
+                });
+
+                // Signature Scheme, PSS is only available for RSA keys
+                if app.use_existing_key || app.key_algorithm == "rsa" {
+                    ui.horizontal(|ui| {
+                        ui.label("Signature Scheme:");
+                        let response = egui::ComboBox::from_id_salt("signature_scheme")
+                            .selected_text(match app.signature_scheme.as_str() {
+                                "pss" => "RSASSA-PSS",
+                                _ => "PKCS#1 v1.5",
+                            })
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut app.signature_scheme, "pkcs1".to_string(), "PKCS#1 v1.5");
+                                ui.selectable_value(&mut app.signature_scheme, "pss".to_string(), "RSASSA-PSS");
+                            }).response;
+                        mark(ui, response, issues, FieldId::SignatureScheme);
+                    });
+
+                    if app.signature_scheme == "pss" {
+                        ui.horizontal(|ui| {
+                            ui.label("PSS Salt Length:");
+                            let response = ui.add(egui::TextEdit::singleline(&mut app.pss_salt_length)
+                                .hint_text("digest, max or bytes")
+                                .desired_width(100.0));
+                            mark(ui, response, issues, FieldId::SignatureScheme);
+                        });
+
+                        ui.horizontal(|ui| {
+                            ui.label("MGF1 Hash:");
+                            egui::ComboBox::from_id_salt("pss_mgf1_hash")
+                                .selected_text(&app.pss_mgf1_hash)
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(&mut app.pss_mgf1_hash, "sha256".to_string(), "SHA-256");
+                                    ui.selectable_value(&mut app.pss_mgf1_hash, "sha384".to_string(), "SHA-384");
+                                    ui.selectable_value(&mut app.pss_mgf1_hash, "sha512".to_string(), "SHA-512");
+                                });
+                        });
+                    }
+                }
+
+                // Key Usage
+                ui.add_space(5.0);
+                ui.horizontal(|ui| {
+                    ui.label("Key Usage:");
+                    badge(ui, issues, FieldId::KeyUsage);
+                });
+                ui.horizontal_wrapped(|ui| {
+                    for (name, label) in KEY_USAGES {
+                        toggle_usage(ui, &mut app.key_usage, name, label);
+                    }
+                });
+
+                // Extended Key Usage
+                ui.add_space(5.0);
+                ui.horizontal(|ui| {
+                    ui.label("Extended Key Usage:");
+                    badge(ui, issues, FieldId::ExtendedKeyUsage);
+                });
+                ui.horizontal_wrapped(|ui| {
+                    for (name, label) in EXTENDED_KEY_USAGES {
+                        toggle_usage(ui, &mut app.extended_key_usage, name, label);
+                    }
                 });
 
-                // Street Address
                 ui.horizontal(|ui| {
-                    ui.label("Street Address:");
-                    ui.add(egui::TextEdit::singleline(&mut app.street_address)
-                        .hint_text("123 Main Street")
+                    ui.label("Custom OID:");
+                    ui.add(egui::TextEdit::singleline(&mut app.current_eku_oid)
+                        .hint_text("1.3.6.1.4.1.311.10.3.4")
                         .desired_width(200.0));
+
+                    let valid = is_valid_oid(app.current_eku_oid.trim());
+                    if ui.add_enabled(valid, egui::Button::new("Add OID")).clicked() {
+                        let oid = app.current_eku_oid.trim().to_string();
+                        if !app.extended_key_usage.contains(&oid) {
+                            app.extended_key_usage.push(oid);
+                        }
+                        app.current_eku_oid.clear();
+                    }
                 });
 
-                // Postal Code
+                // Custom OIDs have no checkbox, list them with a remove button
+                let mut to_remove = None;
+                for (i, usage) in app.extended_key_usage.iter().enumerate() {
+                    if is_valid_oid(usage) {
+                        ui.horizontal(|ui| {
+                            ui.label(format!("[OID] {}", usage));
+                            if ui.button("Remove").clicked() {
+                                to_remove = Some(i);
+                            }
+                        });
+                    }
+                }
+                if let Some(idx) = to_remove {
+                    app.extended_key_usage.remove(idx);
+                }
+
+                ui.add_space(5.0);
+                ui.checkbox(&mut app.ocsp_must_staple, "OCSP Must-Staple")
+                    .on_hover_text("Requests the TLS Feature extension with status_request (RFC 7633)");
+
+                // Microsoft ADCS certificate template
+                ui.add_space(5.0);
                 ui.horizontal(|ui| {
-                    ui.label("Postal Code:");
-                    ui.add(egui::TextEdit::singleline(&mut app.postal_code)
-                        .hint_text("12345")
+                    ui.label("ADCS Template:");
+                    let response = ui.add(egui::TextEdit::singleline(&mut app.adcs_template)
+                        .hint_text("WebServer or template OID")
                         .desired_width(200.0));
+                    mark(ui, response, issues, FieldId::AdcsTemplate);
                 });
+                if is_valid_oid(app.adcs_template.trim()) {
+                    ui.horizontal(|ui| {
+                        ui.label("Template Version:");
+                        let response = ui.add(egui::TextEdit::singleline(&mut app.adcs_template_major_version)
+                            .hint_text("major")
+                            .desired_width(60.0));
+                        mark(ui, response, issues, FieldId::AdcsTemplate);
+                        ui.label(".");
+                        let response = ui.add(egui::TextEdit::singleline(&mut app.adcs_template_minor_version)
+                            .hint_text("minor")
+                            .desired_width(60.0));
+                        mark(ui, response, issues, FieldId::AdcsTemplate);
+                    });
+                }
 
-                // Key Size
+                // Custom extensions
+                ui.add_space(5.0);
                 ui.horizontal(|ui| {
-                    ui.label("Key Size:");
-                    egui::ComboBox::from_id_salt("key_size")
-                        .selected_text(&app.key_size)
-                        .show_ui(ui, |ui| {
-                            ui.selectable_value(&mut app.key_size, "2048".to_string(), "2048 bits");
-                            ui.selectable_value(&mut app.key_size, "4096".to_string(), "4096 bits");
-                        });
+                    ui.label("Custom Extensions:");
+                    if ui.button("Add Extension").clicked() {
+                        app.custom_extensions.push(CustomExtension::new());
+                    }
                 });
 
-                // Hash Algorithm
+                let mut to_remove = None;
+                for (i, extension) in app.custom_extensions.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.add(egui::TextEdit::singleline(&mut extension.oid)
+                            .hint_text("1.3.6.1.4.1.99999.1")
+                            .desired_width(150.0));
+                        ui.checkbox(&mut extension.critical, "Critical");
+
+                        egui::ComboBox::from_id_salt(("extension_format", i))
+                            .selected_text(extension.format.label())
+                            .width(80.0)
+                            .show_ui(ui, |ui| {
+                                for format in ExtensionValueFormat::ALL {
+                                    ui.selectable_value(&mut extension.format, format, format.label());
+                                }
+                            });
+
+                        ui.add(egui::TextEdit::singleline(&mut extension.value)
+                            .hint_text(extension.format.hint())
+                            .desired_width(180.0));
+
+                        badge(ui, issues, FieldId::CustomExtension(i));
+                        if ui.button("Remove").clicked() {
+                            to_remove = Some(i);
+                        }
+                    });
+                }
+                if let Some(idx) = to_remove {
+                    app.custom_extensions.remove(idx);
+                }
+
+                // PKCS#9 request attributes, e.g. for SCEP enrollment
+                ui.add_space(5.0);
                 ui.horizontal(|ui| {
-                    ui.label("Hash Algorithm:");
-                    egui::ComboBox::from_id_salt("hash_algo")
-                        .selected_text(&app.hash_algorithm)
-                        .show_ui(ui, |ui| {
-                            ui.selectable_value(&mut app.hash_algorithm, "sha256".to_string(), "SHA-256");
-                            ui.selectable_value(&mut app.hash_algorithm, "sha384".to_string(), "SHA-384");
-                            ui.selectable_value(&mut app.hash_algorithm, "sha512".to_string(), "SHA-512");
-                        });
+                    ui.label("Challenge Password:");
+                    let response = ui.add(egui::TextEdit::singleline(&mut app.challenge_password)
+                        .password(true)
+                        .hint_text("optional")
+                        .desired_width(200.0));
+                    mark(ui, response, issues, FieldId::ChallengePassword);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Unstructured Name:");
+                    let response = ui.add(egui::TextEdit::singleline(&mut app.unstructured_name)
+                        .hint_text("optional")
+                        .desired_width(200.0));
+                    mark(ui, response, issues, FieldId::UnstructuredName);
                 });
 
                 ui.separator();
@@ -121,44 +524,70 @@ pub fn render(ui: &mut egui::Ui, app: &mut CertGenApp) {
             // Common Name
             ui.horizontal(|ui| {
                 ui.label("Common Name:");
+                let hint = if app.is_ca_request() { "Example Issuing CA" } else { "mail.test.org" };
+                let previous_cn = app.common_name.clone();
                 let response = ui.add(egui::TextEdit::singleline(&mut app.common_name)
-                    .hint_text("mail.test.org")
+                    .hint_text(hint)
                     .desired_width(200.0));
+                let response = mark(ui, response, issues, FieldId::CommonName);
+                if !app.is_ca_request() {
+                    idn_note(ui, &app.common_name);
+                }
 
-                // Update or add CN as first SAN when it changes
-                if response.changed() {
-                    if !app.common_name.is_empty() {
-                        if app.sans.is_empty() {
-                            app.sans.push(app.common_name.clone());
-                        } else {
-                            app.sans[0] = app.common_name.clone();
-                        }
-                    } else if !app.sans.is_empty() {
-                        app.sans.remove(0);
-                    }
+                // Keep the first SAN mirroring the CN when it changes, CA names are not host names
+                if response.changed() && app.sync_cn_san && !app.is_ca_request() {
+                    sync_cn_san(&mut app.sans, &previous_cn, &app.common_name);
                 }
             });
 
             ui.add_space(5.0);
 
             // Subject Alternative Names section
-            ui.label(egui::RichText::new("Subject Alternative Names:").strong());
+            ui.horizontal(|ui| {
+                ui.label(egui::RichText::new("Subject Alternative Names:").strong());
+                if !app.is_ca_request() {
+                    let response = ui.checkbox(&mut app.sync_cn_san, "First SAN follows the CN")
+                        .on_hover_text("Keeps the Common Name as the first SAN while it is typed");
+                    if response.changed() && app.sync_cn_san {
+                        mirror_cn_san(&mut app.sans, &app.common_name);
+                    }
+                }
+                if !app.sans.is_empty() && ui.button("Normalize")
+                    .on_hover_text("Lower-cases names, removes trailing dots, writes IPv6 addresses canonically and drops duplicates")
+                    .clicked() {
+                    app.sans = normalize_sans(&app.sans);
+                }
+                issue_note(ui, issues, FieldId::SanList);
+            });
 
             ui.horizontal(|ui| {
+                egui::ComboBox::from_id_salt("san_type")
+                    .selected_text(app.current_san_type.map_or("Auto", |san_type| san_type.label()))
+                    .width(80.0)
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut app.current_san_type, None, "Auto");
+                        for san_type in SanType::ALL {
+                            ui.selectable_value(&mut app.current_san_type, Some(san_type), san_type.label());
+                        }
+                    });
+
+                let hint = app.current_san_type.map_or("Enter domain or IP (e.g. www.example.com)", |san_type| san_type.hint());
                 let response = ui.add(egui::TextEdit::singleline(&mut app.current_san)
-                    .hint_text("Enter domain or IP (e.g. www.example.com)")
+                    .hint_text(hint)
                     .desired_width(300.0));
 
                 // Handle Enter key
-                if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
-                    if !app.current_san.is_empty() {
-                        app.sans.push(app.current_san.clone());
-                        app.current_san.clear();
+                let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                if (ui.button("Add SAN").clicked() || submitted) && !app.current_san.trim().is_empty() {
+                    let value = app.current_san.trim().to_string();
+                    let entry = match app.current_san_type {
+                        Some(san_type) => SanEntry::new(san_type, value),
+                        None => SanEntry::detect(value),
+                    };
+                    // A name that is already listed is not added twice
+                    if !app.sans.iter().any(|san| san.is_same(&entry)) {
+                        app.sans.push(entry.normalized());
                     }
-                }
-
-                if ui.button("Add SAN").clicked() && !app.current_san.is_empty() {
-                    app.sans.push(app.current_san.clone());
                     app.current_san.clear();
                 }
             });
@@ -170,23 +599,28 @@ pub fn render(ui: &mut egui::Ui, app: &mut CertGenApp) {
                     .inner_margin(5.0)
                     .show(ui, |ui| {
                         let mut to_remove = None;
+                        let cn = SanEntry::new(SanType::Dns, app.common_name.as_str());
+                        let first_from_cn = app.sync_cn_san && !app.is_ca_request();
 
                         for (i, san) in app.sans.iter().enumerate() {
                             ui.horizontal(|ui| {
                                 // Icon based on type
-                                let icon = if san.parse::<std::net::IpAddr>().is_ok() {
-                                    "IP"
-                                } else {
-                                    "DNS"
-                                };
-                                ui.label(format!("[{}]", icon));
-                                ui.label(san);
+                                ui.label(format!("[{}]", san.san_type.label()));
+                                ui.label(&san.value);
+                                if san.san_type == SanType::Dns {
+                                    idn_note(ui, &san.value);
+                                    if let Some(domain) = public_suffix::registrable_domain(&san.value) {
+                                        ui.label(egui::RichText::new(format!("[{}]", domain)).weak())
+                                            .on_hover_text("Registrable domain the CA validates");
+                                    }
+                                }
+                                issue_note(ui, issues, FieldId::San(i));
 
                                 // Show badge for first SAN (CN)
-                                if i == 0 {
+                                if i == 0 && first_from_cn && san.is_same(&cn) {
                                     ui.label(egui::RichText::new("(from CN)").italics().weak());
                                 } else {
-                                    // Only allow removing SANs after the first one
+                                    // The mirrored CN goes away with the CN, every other entry can be removed
                                     if ui.button("Remove").clicked() {
                                         to_remove = Some(i);
                                     }
@@ -210,3 +644,222 @@ pub fn render(ui: &mut egui::Ui, app: &mut CertGenApp) {
             }
         });
 }
+
+/// Checkbox that adds or removes `name` from a list of usages
+fn toggle_usage(ui: &mut egui::Ui, usages: &mut Vec<String>, name: &str, label: &str) {
+    let mut selected = usages.iter().any(|usage| usage == name);
+    if ui.checkbox(&mut selected, label).changed() {
+        if selected {
+            usages.push(name.to_string());
+        } else {
+            usages.retain(|usage| usage != name);
+        }
+    }
+}
+
+/// Business category, registration number and jurisdiction of incorporation for EV requests
+fn ev_details(ui: &mut egui::Ui, app: &mut CertGenApp, issues: &[Issue]) {
+    ui.horizontal(|ui| {
+        ui.label("Business Category:");
+        let response = egui::ComboBox::from_id_salt("ev_business_category")
+            .selected_text(if app.business_category.is_empty() { "Select" } else { app.business_category.as_str() })
+            .show_ui(ui, |ui| {
+                for category in EV_BUSINESS_CATEGORIES {
+                    ui.selectable_value(&mut app.business_category, category.to_string(), category);
+                }
+            }).response;
+        mark(ui, response, issues, FieldId::BusinessCategory);
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("Jurisdiction Country:");
+        let response = country_picker(ui, "jurisdiction_country", &mut app.jurisdiction_country, &mut app.country_search);
+        mark(ui, response, issues, FieldId::JurisdictionCountry);
+    });
+
+    for (label, value, hint, field) in [
+        ("Jurisdiction State:", &mut app.jurisdiction_state, "optional", FieldId::JurisdictionState),
+        ("Jurisdiction Locality:", &mut app.jurisdiction_locality, "optional, e.g. Münster", FieldId::JurisdictionLocality),
+        ("Registration Number:", &mut app.serial_number, "HRB 12345", FieldId::SerialNumber),
+    ] {
+        ui.horizontal(|ui| {
+            ui.label(label);
+            let response = ui.add(egui::TextEdit::singleline(value)
+                .hint_text(hint)
+                .desired_width(200.0));
+            mark(ui, response, issues, field);
+        });
+    }
+}
+
+/// Combo box over the ISO 3166-1 codes, searchable by code or name, Enter picks the first match
+fn country_picker(ui: &mut egui::Ui, id_salt: &str, code: &mut String, search: &mut String) -> egui::Response {
+    let selected = match countries::country_name(code) {
+        Some(name) => format!("{} - {}", code, name),
+        None if code.is_empty() => "Select".to_string(),
+        None => code.clone(),
+    };
+
+    egui::ComboBox::from_id_salt(id_salt)
+        .selected_text(selected)
+        .width(200.0)
+        .height(300.0)
+        .close_behavior(egui::PopupCloseBehavior::CloseOnClickOutside)
+        .show_ui(ui, |ui| {
+            let response = ui.add(egui::TextEdit::singleline(search)
+                .hint_text("Search code or name")
+                .desired_width(180.0));
+            if ui.memory(|memory| memory.focused().is_none()) {
+                response.request_focus();
+            }
+
+            let matches = countries::search(search);
+            let mut picked = None;
+            if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                picked = matches.first().map(|(known, _)| *known);
+            }
+            for (known, name) in matches {
+                if ui.selectable_label(code == known, format!("{} - {}", known, name)).clicked() {
+                    picked = Some(known);
+                }
+            }
+
+            if let Some(known) = picked {
+                *code = known.to_string();
+                search.clear();
+                ui.close();
+            }
+        }).response
+}
+
+/// Falls back to PKCS#1 v1.5 when the key choice no longer allows RSA-PSS
+fn reset_signature_scheme(app: &mut CertGenApp) {
+    if !app.use_existing_key && app.key_algorithm != "rsa" {
+        app.signature_scheme = "pkcs1".to_string();
+    }
+}
+
+/// Ordered list of subject attributes, the first CN row shows the Common Name field
+fn subject_editor(ui: &mut egui::Ui, app: &mut CertGenApp, issues: &[Issue]) {
+    let cn_index = app.subject.iter().position(|entry| canonical_attribute(entry.attribute.trim()) == "CN");
+    let last = app.subject.len().saturating_sub(1);
+    let mut move_up = None;
+    let mut move_down = None;
+    let mut to_remove = None;
+
+    for (i, entry) in app.subject.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            ui.add_enabled(i > 0, egui::Checkbox::new(&mut entry.multi_valued, "+"))
+                .on_hover_text("Join with the previous entry into one multi-valued RDN");
+
+            egui::ComboBox::from_id_salt(("subject_attribute", i))
+                .selected_text("")
+                .width(20.0)
+                .show_ui(ui, |ui| {
+                    for attribute in SUBJECT_ATTRIBUTES {
+                        ui.selectable_value(&mut entry.attribute, attribute.to_string(), attribute);
+                    }
+                });
+            ui.add(egui::TextEdit::singleline(&mut entry.attribute)
+                .hint_text("OU or OID")
+                .desired_width(100.0));
+
+            if Some(i) == cn_index {
+                let mut common_name = app.common_name.clone();
+                let response = ui.add_enabled(false, egui::TextEdit::singleline(&mut common_name).desired_width(200.0))
+                    .on_disabled_hover_text("Taken from the Common Name field");
+                mark(ui, response, issues, FieldId::CommonName);
+            } else {
+                let response = ui.add(egui::TextEdit::singleline(&mut entry.value).desired_width(200.0));
+                mark(ui, response, issues, FieldId::Subject(i));
+            }
+
+            if ui.add_enabled(i > 0, egui::Button::new("Up")).clicked() {
+                move_up = Some(i);
+            }
+            if ui.add_enabled(i < last, egui::Button::new("Down")).clicked() {
+                move_down = Some(i);
+            }
+            if ui.button("Remove").clicked() {
+                to_remove = Some(i);
+            }
+        });
+    }
+
+    if let Some(idx) = move_up {
+        app.subject.swap(idx - 1, idx);
+    }
+    if let Some(idx) = move_down {
+        app.subject.swap(idx, idx + 1);
+    }
+    if let Some(idx) = to_remove {
+        app.subject.remove(idx);
+    }
+
+    ui.horizontal(|ui| {
+        if ui.button("Add Attribute").clicked() {
+            app.subject.push(SubjectEntry::new("OU", ""));
+        }
+        if ui.button("Reset from fields").clicked() {
+            app.custom_subject = false;
+            app.subject = CertConfig::from(&*app).field_subject();
+            app.custom_subject = true;
+        }
+        badge(ui, issues, FieldId::SubjectList);
+    });
+}
+
+fn issue_color(severity: Severity) -> egui::Color32 {
+    match severity {
+        Severity::Error => egui::Color32::RED,
+        Severity::Warning => egui::Color32::from_rgb(230, 160, 0),
+    }
+}
+
+fn issue_text(issues: &[&Issue]) -> String {
+    issues.iter().map(|issue| issue.message.as_str()).collect::<Vec<_>>().join("\n")
+}
+
+/// Outlines an input that has problems and lists them on hover
+fn mark(ui: &egui::Ui, response: egui::Response, issues: &[Issue], field: FieldId) -> egui::Response {
+    let found = validation::field_issues(issues, field);
+    let Some(worst) = found.first() else {
+        return response;
+    };
+    ui.painter().rect_stroke(response.rect, 2.0, egui::Stroke::new(1.5, issue_color(worst.severity)), egui::StrokeKind::Outside);
+    response.on_hover_text(issue_text(&found))
+}
+
+/// Label for rows without a single input to outline, like list entries
+fn badge(ui: &mut egui::Ui, issues: &[Issue], field: FieldId) {
+    let found = validation::field_issues(issues, field);
+    if let Some(worst) = found.first() {
+        let text = match worst.severity {
+            Severity::Error => "invalid",
+            Severity::Warning => "warning",
+        };
+        ui.label(egui::RichText::new(text).color(issue_color(worst.severity)))
+            .on_hover_text(issue_text(&found));
+    }
+}
+
+/// Shows the other form of an internationalized domain name, the A-label for typed Unicode and vice versa
+fn idn_note(ui: &mut egui::Ui, name: &str) {
+    let other = if name.is_ascii() {
+        dns_to_unicode(name)
+    } else {
+        dns_to_ascii(name).ok()
+    };
+    if let Some(other) = other.filter(|other| other != name) {
+        ui.label(egui::RichText::new(format!("({})", other)).weak());
+    }
+}
+
+/// Spells out the most severe problem of a list entry next to it, the others on hover
+fn issue_note(ui: &mut egui::Ui, issues: &[Issue], field: FieldId) {
+    let found = validation::field_issues(issues, field);
+    if let Some(worst) = found.first() {
+        ui.label(egui::RichText::new(&worst.message).small().color(issue_color(worst.severity)))
+            .on_hover_text(issue_text(&found));
+    }
+}
@@ -7,9 +7,14 @@ pub mod openssloutput;
 pub mod save_button;
 pub mod execute_button;
 
-pub fn generate_and_save(cnf: &str, name: &str, key: &str, csr: &str) -> std::io::Result<()> {
+/// Bundles the request files into a zip in the downloads folder.
+/// `key` is `None` when the request was made with an existing key, the recreate command then references that key file.
+/// `key_encrypted` adds a note to the zip that the key needs its passphrase.
+pub fn generate_and_save(cnf: &str, name: &str, key: Option<&str>, csr: &str, command: &str, key_encrypted: bool) -> std::io::Result<()> {
     log::debug!("Generating and saving files to zip");
-    log::debug!("Contents: \n{name}.cnf = {cnf}\n\n{name}.key = {key}\n\n{name}.csr = {csr}\n\ncommand: openssl req -new -out {name}.csr -config {name}.cnf");
+    // Only names and sizes are logged, the key and the CSR with its challenge password never are
+    log::debug!("Contents: {name}.cnf ({} bytes), {name}.key ({}), {name}.csr ({} bytes), recreate_command.txt ({} bytes)",
+        cnf.len(), key.map_or("not included".to_string(), |key| format!("{} bytes", key.len())), csr.len(), command.len());
     // Create zip file in memory
     let mut zip_buffer = Cursor::new(Vec::new());
     let mut zip = ZipWriter::new(&mut zip_buffer);
@@ -19,14 +24,24 @@ pub fn generate_and_save(cnf: &str, name: &str, key: &str, csr: &str) -> std::io
     zip.start_file(format!("{}.cnf", name), options)?;
     zip.write_all(cnf.as_bytes())?;
 
-    zip.start_file(format!("{}.key", name), options)?;
-    zip.write_all(key.as_bytes())?;
+    if let Some(key) = key {
+        zip.start_file(format!("{}.key", name), options)?;
+        zip.write_all(key.as_bytes())?;
+
+        if key_encrypted {
+            zip.start_file("key_encrypted.txt", options)?;
+            zip.write_all(format!(
+                "{}.key is an encrypted PKCS#8 private key (AES-256-CBC).\nThe passphrase is not part of this archive, OpenSSL asks for it when the key is used.\n",
+                name
+            ).as_bytes())?;
+        }
+    }
 
     zip.start_file(format!("{}.csr", name), options)?;
     zip.write_all(csr.as_bytes())?;
 
     zip.start_file("recreate_command.txt", options)?;
-    zip.write_all(format!("openssl req -new -out {}.csr -config {}.cnf", name, name).as_bytes())?;
+    zip.write_all(command.as_bytes())?;
 
     // Finalize the zip
     zip.finish()?;
@@ -36,7 +51,7 @@ pub fn generate_and_save(cnf: &str, name: &str, key: &str, csr: &str) -> std::io
 
     if let Some(path) = dirs::download_dir() {
         let target = path.join(format!("{}_certificate_files.zip", name));
-        log::info!("Writing zip to {}", target.display());
+        log::info!("Writing zip ({} bytes) to {}", zip_data.len(), target.display());
         fs::write(target, zip_data)?;
     }
     Ok(())
@@ -1,5 +1,6 @@
 use eframe::egui;
 use crate::CertGenApp;
+use crate::cert_config::CertConfig;
 use super::generate_and_save;
 
 pub fn render(ui: &mut egui::Ui, app: &mut CertGenApp) {
@@ -7,12 +8,11 @@ pub fn render(ui: &mut egui::Ui, app: &mut CertGenApp) {
 
     if ui.button(button_text).clicked() {
         // Get file contents
-        let cnf = app.config_output.clone();
-        let name = app.common_name.clone();
-        let key = app.key_content.clone();
-        let csr = app.csr_content.clone();
+        let config = CertConfig::from(&*app);
+        let name = config.file_name();
+        let command = config.req_command(&format!("{}.cnf", name));
 
-        match generate_and_save(&cnf, &name, &key, &csr) {
+        match generate_and_save(&app.config_output, &name, app.new_key_content(), &app.csr_content, &command, app.encrypts_new_key()) {
             Ok(_) => {
                 log::info!("Certificate files saved successfully");
             }
@@ -0,0 +1,197 @@
+//! Minimal DER encoding for the extension values the openssl crate has no builder for,
+//! and the matching reader for the parts of a request it has no accessor for.
+
+use std::io;
+
+pub const TAG_BOOLEAN: u8 = 0x01;
+pub const TAG_INTEGER: u8 = 0x02;
+pub const TAG_OCTET_STRING: u8 = 0x04;
+pub const TAG_OID: u8 = 0x06;
+pub const TAG_UTF8_STRING: u8 = 0x0c;
+pub const TAG_BMP_STRING: u8 = 0x1e;
+pub const TAG_SEQUENCE: u8 = 0x30;
+pub const TAG_SET: u8 = 0x31;
+
+/// Context specific tag `[n]`, primitive or constructed
+pub fn context_tag(n: u8, constructed: bool) -> u8 {
+    0x80 | if constructed { 0x20 } else { 0 } | n
+}
+
+/// Encodes a single tag-length-value element
+pub fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    let len = content.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes: Vec<u8> = len.to_be_bytes().into_iter().skip_while(|b| *b == 0).collect();
+        out.push(0x80 | bytes.len() as u8);
+        out.extend(bytes);
+    }
+    out.extend_from_slice(content);
+    out
+}
+
+pub fn sequence(items: &[Vec<u8>]) -> Vec<u8> {
+    tlv(TAG_SEQUENCE, &items.concat())
+}
+
+/// Minimal two's complement encoding of a non-negative integer
+pub fn integer(value: u64) -> Vec<u8> {
+    let mut bytes: Vec<u8> = value.to_be_bytes().into_iter().skip_while(|b| *b == 0).collect();
+    if bytes.first().is_none_or(|b| b & 0x80 != 0) {
+        bytes.insert(0, 0);
+    }
+    tlv(TAG_INTEGER, &bytes)
+}
+
+pub fn utf8_string(value: &str) -> Vec<u8> {
+    tlv(TAG_UTF8_STRING, value.as_bytes())
+}
+
+/// BMPString, big endian UTF-16
+pub fn bmp_string(value: &str) -> Vec<u8> {
+    let content: Vec<u8> = value.encode_utf16().flat_map(u16::to_be_bytes).collect();
+    tlv(TAG_BMP_STRING, &content)
+}
+
+/// Content octets of a dotted OID, without tag and length
+pub fn oid_content(dotted: &str) -> io::Result<Vec<u8>> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid OID: {}", dotted));
+
+    let arcs = dotted.split('.')
+        .map(|arc| arc.parse::<u64>().map_err(|_| invalid()))
+        .collect::<io::Result<Vec<u64>>>()?;
+    if arcs.len() < 2 || arcs[0] > 2 || (arcs[0] < 2 && arcs[1] >= 40) {
+        return Err(invalid());
+    }
+
+    let mut out = Vec::new();
+    let first = arcs[0].checked_mul(40).and_then(|v| v.checked_add(arcs[1])).ok_or_else(invalid)?;
+    for arc in std::iter::once(first).chain(arcs[2..].iter().copied()) {
+        let mut chunk = vec![(arc & 0x7f) as u8];
+        let mut rest = arc >> 7;
+        while rest > 0 {
+            chunk.push(0x80 | (rest & 0x7f) as u8);
+            rest >>= 7;
+        }
+        out.extend(chunk.iter().rev());
+    }
+    Ok(out)
+}
+
+pub fn oid(dotted: &str) -> io::Result<Vec<u8>> {
+    Ok(tlv(TAG_OID, &oid_content(dotted)?))
+}
+
+/// Splits the first element off `input`, returns its tag, its content and the rest
+pub fn read(input: &[u8]) -> io::Result<(u8, &[u8], &[u8])> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "Truncated or malformed DER");
+
+    let (&tag, rest) = input.split_first().ok_or_else(invalid)?;
+    let (&first, rest) = rest.split_first().ok_or_else(invalid)?;
+    let (len, rest) = if first < 0x80 {
+        (first as usize, rest)
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count == 0 || count > size_of::<usize>() || rest.len() < count {
+            return Err(invalid());
+        }
+        let len = rest[..count].iter().fold(0usize, |len, b| (len << 8) | *b as usize);
+        (len, &rest[count..])
+    };
+    if rest.len() < len {
+        return Err(invalid());
+    }
+    Ok((tag, &rest[..len], &rest[len..]))
+}
+
+/// Tags and contents of all elements inside a SEQUENCE or SET
+pub fn elements(mut content: &[u8]) -> io::Result<Vec<(u8, &[u8])>> {
+    let mut out = Vec::new();
+    while !content.is_empty() {
+        let (tag, value, rest) = read(content)?;
+        out.push((tag, value));
+        content = rest;
+    }
+    Ok(out)
+}
+
+/// Dotted form of OID content octets, the reverse of [`oid_content`]
+pub fn oid_to_dotted(content: &[u8]) -> io::Result<String> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "Malformed OID");
+
+    let mut arcs = Vec::new();
+    let mut arc: u64 = 0;
+    for b in content {
+        arc = arc.checked_mul(128).ok_or_else(invalid)? | (b & 0x7f) as u64;
+        if b & 0x80 == 0 {
+            arcs.push(arc);
+            arc = 0;
+        }
+    }
+    if arcs.is_empty() || content.last().is_some_and(|b| b & 0x80 != 0) {
+        return Err(invalid());
+    }
+
+    let first = arcs[0];
+    let (a, b) = if first < 80 { (first / 40, first % 40) } else { (2, first - 80) };
+    Ok(std::iter::once(a).chain(std::iter::once(b)).chain(arcs[1..].iter().copied())
+        .map(|arc| arc.to_string())
+        .collect::<Vec<_>>()
+        .join("."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_oid_encoding() {
+        // id-pe-tlsfeature
+        assert_eq!(oid("1.3.6.1.5.5.7.1.24").unwrap(), vec![0x06, 0x08, 0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x01, 0x18]);
+        // Microsoft UPN, multi byte arc 311
+        assert_eq!(oid_content("1.3.6.1.4.1.311.20.2.3").unwrap(), vec![0x2b, 0x06, 0x01, 0x04, 0x01, 0x82, 0x37, 0x14, 0x02, 0x03]);
+        assert!(oid("1.40").is_err());
+        assert!(oid("1.3.x").is_err());
+    }
+
+    #[test]
+    fn test_read_round_trip() {
+        let encoded = sequence(&[oid("2.5.29.19").unwrap(), integer(300), tlv(TAG_OCTET_STRING, &[0u8; 200])]);
+        let (tag, content, rest) = read(&encoded).unwrap();
+        assert_eq!(tag, TAG_SEQUENCE);
+        assert!(rest.is_empty());
+
+        let items = elements(content).unwrap();
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0].0, TAG_OID);
+        assert_eq!(oid_to_dotted(items[0].1).unwrap(), "2.5.29.19");
+        assert_eq!(items[1], (TAG_INTEGER, &[0x01, 0x2c][..]));
+        assert_eq!(items[2].1.len(), 200);
+
+        assert_eq!(oid_to_dotted(&oid_content("1.3.6.1.4.1.311.20.2.3").unwrap()).unwrap(), "1.3.6.1.4.1.311.20.2.3");
+        assert!(read(&encoded[..encoded.len() - 1]).is_err());
+        assert!(oid_to_dotted(&[0x2b, 0x86]).is_err());
+    }
+
+    #[test]
+    fn test_integer_encoding() {
+        assert_eq!(integer(0), vec![0x02, 0x01, 0x00]);
+        assert_eq!(integer(5), vec![0x02, 0x01, 0x05]);
+        assert_eq!(integer(128), vec![0x02, 0x02, 0x00, 0x80]);
+        assert_eq!(integer(256), vec![0x02, 0x02, 0x01, 0x00]);
+    }
+
+    #[test]
+    fn test_bmp_string_encoding() {
+        assert_eq!(bmp_string("Web"), vec![0x1e, 0x06, 0x00, 0x57, 0x00, 0x65, 0x00, 0x62]);
+    }
+
+    #[test]
+    fn test_length_encoding() {
+        assert_eq!(tlv(0x04, &[0u8; 5])[..2], [0x04, 0x05]);
+        assert_eq!(tlv(0x04, &[0u8; 200])[..3], [0x04, 0x81, 200]);
+        assert_eq!(tlv(0x04, &[0u8; 300])[..4], [0x04, 0x82, 0x01, 0x2c]);
+    }
+}
@@ -0,0 +1,227 @@
+//! CA/Browser Forum Baseline Requirements lint over a request, before it is sent to a CA.
+//! Works on the form values and on requests read back from PEM, so both backends are checked the same way.
+
+use std::net::IpAddr;
+use crate::cert_config::{CertConfig, SanEntry, SanType, SubjectEntry, is_eddsa};
+use crate::public_suffix;
+use crate::validation::Severity;
+
+/// Lint policies as (mode, display name). A public CA follows the Baseline Requirements,
+/// a private CA only gets warnings about what is weak or deprecated.
+pub const LINT_POLICIES: [(&str, &str); 2] = [
+    ("public", "Public CA"),
+    ("private", "Private CA"),
+];
+
+/// What the linter needs to know about a request, independent of where it came from
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RequestFacts {
+    pub subject: Vec<SubjectEntry>,
+    pub san: Vec<SanEntry>,
+    /// "rsa", "ec", "ed25519" or "ed448", empty when unknown (e.g. an existing key before it is read)
+    pub key_algorithm: String,
+    pub key_bits: Option<u32>,
+    /// OpenSSL curve name like "prime256v1"
+    pub ec_curve: Option<String>,
+    /// Lower case digest like "sha256", `None` for EdDSA or when it cannot be told
+    pub hash_algorithm: Option<String>,
+    pub is_ca: bool,
+}
+
+impl RequestFacts {
+    pub fn from_config(config: &CertConfig) -> Self {
+        let new_key = config.existing_key.is_none();
+        let key_algorithm = if new_key { config.key_algorithm.to_string() } else { String::new() };
+        RequestFacts {
+            subject: config.subject_entries(),
+            san: config.san_entries(),
+            key_bits: (key_algorithm == "rsa").then(|| config.key_size.parse().ok()).flatten(),
+            ec_curve: (key_algorithm == "ec").then(|| config.ec_curve.to_string()),
+            hash_algorithm: (!new_key || !is_eddsa(config.key_algorithm)).then(|| config.hash_algorithm.to_string()),
+            key_algorithm,
+            is_ca: config.is_ca_request(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintFinding {
+    pub severity: Severity,
+    /// Short stable name of the rule, e.g. "internal-name"
+    pub rule: &'static str,
+    pub message: String,
+}
+
+/// Addresses a public CA never certifies: private, loopback, link-local, shared, documentation and reserved ranges
+pub fn is_reserved_ip(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            ip.is_private() || ip.is_loopback() || ip.is_link_local() || ip.is_unspecified() || ip.is_broadcast()
+                || ip.is_documentation() || a == 0 || a >= 240
+                // 100.64.0.0/10 carrier-grade NAT, 198.18.0.0/15 benchmarking
+                || (a == 100 && (64..128).contains(&b)) || (a == 198 && (b == 18 || b == 19))
+        }
+        IpAddr::V6(ip) => {
+            let first = ip.segments()[0];
+            ip.is_loopback() || ip.is_unspecified()
+                // fc00::/7 unique local, fe80::/10 link-local, 2001:db8::/32 documentation
+                || (first & 0xfe00) == 0xfc00 || (first & 0xffc0) == 0xfe80 || (first == 0x2001 && ip.segments()[1] == 0x0db8)
+                || ip.to_ipv4_mapped().is_some_and(|ip| is_reserved_ip(&IpAddr::V4(ip)))
+        }
+    }
+}
+
+/// Runs every rule, under "public" Baseline Requirements violations are errors the CA would reject
+pub fn lint(facts: &RequestFacts, policy: &str) -> Vec<LintFinding> {
+    let public = policy == "public";
+    let mut findings = Vec::new();
+    let mut add = |severity: Severity, rule: &'static str, message: String| findings.push(LintFinding { severity, rule, message });
+    let public_error = if public { Severity::Error } else { Severity::Warning };
+
+    // Host name rules only make sense for end entity TLS requests
+    if !facts.is_ca {
+        if public && facts.san.is_empty() {
+            add(Severity::Error, "no-san", "Public CAs require at least one SAN (BR 7.1.2.7.12)".to_string());
+        }
+        for san in &facts.san {
+            match san.san_type {
+                _ if !public => {}
+                SanType::Dns if public_suffix::registrable_domain(&san.value).is_none() => {
+                    add(Severity::Error, "internal-name", format!("{} is not under a public top-level domain, public CAs do not certify internal names (BR 4.2.2)", san.value));
+                }
+                SanType::Ip if san.value.parse::<IpAddr>().is_ok_and(|ip| is_reserved_ip(&ip)) => {
+                    add(Severity::Error, "reserved-ip", format!("{} is a reserved IP address, public CAs do not certify it (BR 7.1.2.7.12)", san.value));
+                }
+                SanType::Dns | SanType::Ip => {}
+                _ => {
+                    add(Severity::Error, "san-type", format!("{} is neither a DNS name nor an IP address, TLS certificates from public CAs carry only those (BR 7.1.2.7.12)", san));
+                }
+            }
+        }
+
+        // BR 7.1.4.3, a CN is optional but must repeat one of the SANs
+        let cn = facts.subject.iter().find(|entry| entry.attribute == "CN");
+        if let Some(cn) = cn && !facts.san.iter().any(|san| san.value.eq_ignore_ascii_case(&cn.value)) {
+            add(public_error, "cn-not-in-san", format!("Common Name {} is not one of the SANs (BR 7.1.4.3)", cn.value));
+        }
+    }
+
+    for entry in &facts.subject {
+        match entry.attribute.as_str() {
+            "OU" if public => add(Severity::Error, "subject-ou", format!("organizationalUnitName {} is no longer allowed by public CAs (BR 7.1.4.3.1)", entry.value)),
+            "emailAddress" => add(public_error, "subject-email", format!("emailAddress {} in the subject is deprecated, use an Email SAN instead (RFC 5280 4.1.2.6)", entry.value)),
+            _ => {}
+        }
+    }
+
+    match (facts.key_algorithm.as_str(), facts.key_bits) {
+        ("rsa", Some(bits)) if bits < 2048 => {
+            add(public_error, "weak-rsa-key", format!("RSA key of {} bits, at least 2048 are required (BR 6.1.5)", bits));
+        }
+        ("ec", _) if public => {
+            match facts.ec_curve.as_deref() {
+                Some("prime256v1" | "secp384r1") => {}
+                Some("secp521r1") => add(Severity::Warning, "ec-curve", "P-521 is allowed by the Baseline Requirements but not accepted by every root program".to_string()),
+                curve => add(Severity::Error, "ec-curve", format!("Curve {} is not allowed, use P-256 or P-384 (BR 6.1.5)", curve.unwrap_or("unknown"))),
+            }
+        }
+        ("ed25519" | "ed448", _) if public => {
+            add(Severity::Error, "eddsa-key", format!("{} keys are not allowed by the Baseline Requirements, use RSA or ECDSA (BR 6.1.5)", facts.key_algorithm));
+        }
+        _ => {}
+    }
+
+    if let Some(hash) = facts.hash_algorithm.as_deref() && matches!(hash, "sha1" | "md5") {
+        add(public_error, "weak-hash", format!("{} signatures are broken, use SHA-256 or better (BR 7.1.3.2)", hash.to_ascii_uppercase()));
+    }
+
+    findings
+}
+
+/// Findings as lines for the output panel
+pub fn report(findings: &[LintFinding], policy: &str) -> String {
+    let policy_name = LINT_POLICIES.iter().find(|(mode, _)| *mode == policy).map_or(policy, |(_, name)| *name);
+    if findings.is_empty() {
+        return format!("Lint ({}): no findings\n", policy_name);
+    }
+    findings.iter()
+        .map(|finding| {
+            let level = match finding.severity {
+                Severity::Error => "reject",
+                Severity::Warning => "warning",
+            };
+            format!("Lint ({}) {}: {} [{}]\n", policy_name, level, finding.message, finding.rule)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cert_config::tests::test_config;
+
+    fn rules(findings: &[LintFinding]) -> Vec<&'static str> {
+        findings.iter().map(|finding| finding.rule).collect()
+    }
+
+    #[test]
+    fn test_clean_request() {
+        let san = vec![SanEntry::detect("test.example.com")];
+        let facts = RequestFacts::from_config(&test_config(&san));
+        assert!(lint(&facts, "public").is_empty());
+        assert_eq!(report(&[], "public"), "Lint (Public CA): no findings\n");
+    }
+
+    #[test]
+    fn test_public_policy() {
+        let san = vec![
+            SanEntry::detect("intranet.corp"),
+            SanEntry::detect("10.0.0.1"),
+            SanEntry::detect("8.8.8.8"),
+            SanEntry::detect("admin@example.com"),
+        ];
+        let mut config = test_config(&san);
+        config.organizational_unit = Some("IT");
+        config.email = Some("admin@example.com");
+        config.key_size = "1024";
+        config.hash_algorithm = "sha1";
+
+        let facts = RequestFacts::from_config(&config);
+        let findings = lint(&facts, "public");
+        assert_eq!(rules(&findings), ["internal-name", "reserved-ip", "san-type", "cn-not-in-san", "subject-ou", "subject-email", "weak-rsa-key", "weak-hash"]);
+        assert!(findings.iter().all(|finding| finding.severity == Severity::Error));
+
+        // A private CA gets the weak and deprecated parts as warnings only
+        let findings = lint(&facts, "private");
+        assert_eq!(rules(&findings), ["cn-not-in-san", "subject-email", "weak-rsa-key", "weak-hash"]);
+        assert!(findings.iter().all(|finding| finding.severity == Severity::Warning));
+        assert!(report(&findings, "private").starts_with("Lint (Private CA) warning: Common Name test.example.com is not one of the SANs"));
+    }
+
+    #[test]
+    fn test_key_rules() {
+        let san = vec![SanEntry::detect("test.example.com")];
+        let mut config = test_config(&san);
+        config.key_algorithm = "ed25519";
+        let facts = RequestFacts::from_config(&config);
+        assert_eq!(facts.hash_algorithm, None);
+        assert_eq!(rules(&lint(&facts, "public")), ["eddsa-key"]);
+        assert!(lint(&facts, "private").is_empty());
+
+        config.key_algorithm = "ec";
+        config.ec_curve = "secp521r1";
+        let findings = lint(&RequestFacts::from_config(&config), "public");
+        assert_eq!(findings[0].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn test_reserved_ips() {
+        for reserved in ["10.1.2.3", "172.16.0.1", "192.168.1.1", "127.0.0.1", "169.254.1.1", "100.64.0.1", "192.0.2.1", "::1", "fd00::1", "fe80::1", "2001:db8::1", "::ffff:10.0.0.1"] {
+            assert!(is_reserved_ip(&reserved.parse().unwrap()), "{}", reserved);
+        }
+        for public in ["8.8.8.8", "1.1.1.1", "2606:4700::1111"] {
+            assert!(!is_reserved_ip(&public.parse().unwrap()), "{}", public);
+        }
+    }
+}
@@ -7,11 +7,19 @@ use eframe::{egui, CreationContext};
 #[cfg(debug_assertions)]
 use fake::{Fake, Rng};
 use log::LevelFilter;
-use cert_config::CertConfig;
+use cert_config::{CertConfig, CustomExtension, SanEntry, SanType, SubjectEntry};
 
 mod components;
 mod cert_config;
+mod countries;
+#[cfg(feature = "openssl-native")]
+mod der;
 mod openssl_cli;
+mod openssl_conf;
+mod lint;
+mod passphrase;
+mod public_suffix;
+mod validation;
 #[cfg(feature = "openssl-native")]
 mod openssl_native;
 
@@ -20,6 +28,7 @@ use components::openssloutput;
 use components::execute_button;
 use components::save_button;
 use crate::cert_config::sanitize;
+use crate::validation::Severity;
 
 fn setup_logger() {
     let current_time = time::OffsetDateTime::now_local().unwrap_or(time::OffsetDateTime::now_utc());
@@ -70,8 +79,14 @@ pub struct CertGenApp {
     pub locality: String,
     pub organization: String,
     pub common_name: String,
-    pub sans: Vec<String>,
+    pub sans: Vec<SanEntry>,
     pub current_san: String,
+    /// `None` detects the type from the entered value
+    pub current_san_type: Option<SanType>,
+    /// Ordered subject editor, used instead of the fixed fields when `custom_subject` is set
+    pub custom_subject: bool,
+    pub subject: Vec<SubjectEntry>,
+    pub string_encoding: String,
 
     // Advanced mode fields
     pub advanced_mode: bool,
@@ -79,8 +94,47 @@ pub struct CertGenApp {
     pub email: String,
     pub street_address: String,
     pub postal_code: String,
+    pub serial_number: String,
+    pub title: String,
+    pub given_name: String,
+    pub surname: String,
+    pub pseudonym: String,
+    pub organization_identifier: String,
+    pub business_category: String,
+    pub domain_component: String,
+    pub jurisdiction_country: String,
+    pub jurisdiction_state: String,
+    pub jurisdiction_locality: String,
+    pub key_usage: Vec<String>,
+    pub extended_key_usage: Vec<String>,
+    pub current_eku_oid: String,
+    /// Search text of the open country picker
+    pub country_search: String,
+    pub custom_extensions: Vec<CustomExtension>,
+    pub ocsp_must_staple: bool,
+    pub adcs_template: String,
+    pub adcs_template_major_version: String,
+    pub adcs_template_minor_version: String,
+    pub request_profile: String,
+    /// "public" or "private", which rules the request lint applies. Kept when the form is cleared.
+    pub lint_policy: String,
+    /// Whether the first SAN follows the common name while it is typed. Kept when the form is cleared.
+    pub sync_cn_san: bool,
+    pub ca_path_len: String,
+    pub key_algorithm: String,
     pub key_size: String,
+    pub ec_curve: String,
     pub hash_algorithm: String,
+    pub signature_scheme: String,
+    pub pss_salt_length: String,
+    pub pss_mgf1_hash: String,
+    pub use_existing_key: bool,
+    pub existing_key_path: String,
+    pub existing_key_passphrase: String,
+    pub key_passphrase: String,
+    pub key_passphrase_confirm: String,
+    pub challenge_password: String,
+    pub unstructured_name: String,
 
     // Output state
     pub openssl_output: String,
@@ -114,13 +168,53 @@ impl CertGenApp {
             common_name: String::new(),
             sans: Vec::new(),
             current_san: String::new(),
+            current_san_type: None,
+            custom_subject: false,
+            subject: Vec::new(),
+            string_encoding: "ascii".to_string(),
             advanced_mode: false,
             organizational_unit: String::new(),
             email: String::new(),
             street_address: String::new(),
             postal_code: String::new(),
+            serial_number: String::new(),
+            title: String::new(),
+            given_name: String::new(),
+            surname: String::new(),
+            pseudonym: String::new(),
+            organization_identifier: String::new(),
+            business_category: String::new(),
+            domain_component: String::new(),
+            jurisdiction_country: String::new(),
+            jurisdiction_state: String::new(),
+            jurisdiction_locality: String::new(),
+            key_usage: Vec::new(),
+            extended_key_usage: Vec::new(),
+            current_eku_oid: String::new(),
+            country_search: String::new(),
+            custom_extensions: Vec::new(),
+            ocsp_must_staple: false,
+            adcs_template: String::new(),
+            adcs_template_major_version: String::new(),
+            adcs_template_minor_version: String::new(),
+            request_profile: "end_entity".to_string(),
+            lint_policy: "public".to_string(),
+            sync_cn_san: true,
+            ca_path_len: String::new(),
+            key_algorithm: "rsa".to_string(),
             key_size: "2048".to_string(),
+            ec_curve: "prime256v1".to_string(),
             hash_algorithm: "sha256".to_string(),
+            signature_scheme: "pkcs1".to_string(),
+            pss_salt_length: "digest".to_string(),
+            pss_mgf1_hash: "sha256".to_string(),
+            use_existing_key: false,
+            existing_key_path: String::new(),
+            existing_key_passphrase: String::new(),
+            key_passphrase: String::new(),
+            key_passphrase_confirm: String::new(),
+            challenge_password: String::new(),
+            unstructured_name: String::new(),
             openssl_output: String::new(),
             config_output: String::new(),
             key_content: String::new(),
@@ -135,33 +229,22 @@ impl CertGenApp {
         // Clear previous output
         self.openssl_output.clear();
 
-        log::debug!("Input: Country: {}, State: {}, Locality: {}, Organization: {}, Common Name: {}, SAN: {:?}, Key Size: {}, Hash Algorithm: {}", self.country, self.state, self.locality, self.organization, self.common_name, self.sans, self.key_size, self.hash_algorithm);
+        log::debug!("Input: Country: {}, State: {}, Locality: {}, Organization: {}, Common Name: {}, Subject: {:?}, SAN: {:?}, Key Usage: {:?}, Extended Key Usage: {:?}, Custom Extensions: {:?}, Key Algorithm: {}, Key Size: {}, Curve: {}, Hash Algorithm: {}, Signature Scheme: {}", self.country, self.state, self.locality, self.organization, self.common_name, CertConfig::from(&*self).subject_entries(), self.sans, self.key_usage, self.extended_key_usage, self.custom_extensions, self.key_algorithm, self.key_size, self.ec_curve, self.hash_algorithm, self.signature_scheme);
 
-        // Validate country code
-        if self.country.len() != 2 {
-            self.openssl_output.push_str("Error: Country code must be exactly 2 letters\n");
-            return;
-        }
-        if !self.country.chars().all(|c| c.is_alphabetic()) {
-            self.openssl_output.push_str("Error: Country code must contain only letters\n");
-            return;
-        }
+        // Keep country codes the way they are written to the request
+        self.country = countries::normalize(&self.country);
+        self.jurisdiction_country = countries::normalize(&self.jurisdiction_country);
 
-        // Validate required fields
-        if self.common_name.trim().is_empty() {
-            self.openssl_output.push_str("Error: Common Name is required\n");
-            return;
+        // Report every problem at once, the form marks the same issues on the fields
+        let issues = validation::validate_app(self);
+        for issue in &issues {
+            let prefix = match issue.severity {
+                Severity::Error => "Error",
+                Severity::Warning => "Warning",
+            };
+            self.openssl_output.push_str(&format!("{}: {}\n", prefix, issue.message));
         }
-        if self.organization.trim().is_empty() {
-            self.openssl_output.push_str("Error: Organization is required\n");
-            return;
-        }
-        if self.locality.trim().is_empty() {
-            self.openssl_output.push_str("Error: Locality (city) is required\n");
-            return;
-        }
-        if self.state.trim().is_empty() {
-            self.openssl_output.push_str("Error: State/Province is required\n");
+        if validation::has_errors(&issues) {
             return;
         }
 
@@ -174,6 +257,10 @@ impl CertGenApp {
                 self.openssl_output.push_str(&config_text);
                 self.openssl_output.push_str("------------------- Openssl config end ----------------------\n");
                 self.config_output = config_text;
+
+                let facts = lint::RequestFacts::from_config(&CertConfig::from(&*self));
+                let report = lint::report(&lint::lint(&facts, &self.lint_policy), &self.lint_policy);
+                self.openssl_output.push_str(&report);
             }
             Err(err) => {
                 log::error!("Error generating config: {}\n", err);
@@ -182,6 +269,41 @@ impl CertGenApp {
         }
     }
 
+    /// CA requests name an authority instead of a host, so the CN is not kept in sync with the SANs
+    pub fn is_ca_request(&self) -> bool {
+        self.advanced_mode && self.request_profile == "ca"
+    }
+
+    /// Extended Validation requests carry the jurisdiction of incorporation
+    pub fn is_ev_request(&self) -> bool {
+        self.advanced_mode && self.request_profile == "ev"
+    }
+
+    /// Whether the subject comes from the ordered editor instead of the fixed fields
+    pub fn uses_custom_subject(&self) -> bool {
+        self.advanced_mode && self.custom_subject
+    }
+
+    /// Whether the request is built from an existing key instead of a freshly generated one
+    pub fn reuses_existing_key(&self) -> bool {
+        self.advanced_mode && self.use_existing_key
+    }
+
+    /// Whether the newly generated key is written encrypted with the key passphrase
+    pub fn encrypts_new_key(&self) -> bool {
+        self.advanced_mode && !self.use_existing_key && !self.key_passphrase.is_empty()
+    }
+
+    /// The generated key that belongs into the zip, `None` when an existing key was reused
+    pub fn new_key_content(&self) -> Option<&str> {
+        if self.reuses_existing_key() { None } else { Some(&self.key_content) }
+    }
+
+    /// Whether everything that goes into the zip has been generated
+    pub fn has_request_files(&self) -> bool {
+        !self.csr_content.is_empty() && (self.reuses_existing_key() || !self.key_content.is_empty())
+    }
+
     fn clear_form(&mut self) {
         log::debug!("Clearing form");
         self.country.clear();
@@ -191,12 +313,50 @@ impl CertGenApp {
         self.common_name.clear();
         self.sans.clear();
         self.current_san.clear();
+        self.current_san_type = None;
+        self.custom_subject = false;
+        self.subject.clear();
+        self.string_encoding = "ascii".to_string();
         self.organizational_unit.clear();
         self.email.clear();
         self.street_address.clear();
         self.postal_code.clear();
+        self.serial_number.clear();
+        self.title.clear();
+        self.given_name.clear();
+        self.surname.clear();
+        self.pseudonym.clear();
+        self.organization_identifier.clear();
+        self.business_category.clear();
+        self.domain_component.clear();
+        self.jurisdiction_country.clear();
+        self.jurisdiction_state.clear();
+        self.jurisdiction_locality.clear();
+        self.key_usage.clear();
+        self.extended_key_usage.clear();
+        self.current_eku_oid.clear();
+        self.country_search.clear();
+        self.custom_extensions.clear();
+        self.ocsp_must_staple = false;
+        self.adcs_template.clear();
+        self.adcs_template_major_version.clear();
+        self.adcs_template_minor_version.clear();
+        self.request_profile = "end_entity".to_string();
+        self.ca_path_len.clear();
+        self.key_algorithm = "rsa".to_string();
         self.key_size = "2048".to_string();
+        self.ec_curve = "prime256v1".to_string();
         self.hash_algorithm = "sha256".to_string();
+        self.signature_scheme = "pkcs1".to_string();
+        self.pss_salt_length = "digest".to_string();
+        self.pss_mgf1_hash = "sha256".to_string();
+        self.use_existing_key = false;
+        self.existing_key_path.clear();
+        self.existing_key_passphrase.clear();
+        self.key_passphrase.clear();
+        self.key_passphrase_confirm.clear();
+        self.challenge_password.clear();
+        self.unstructured_name.clear();
         self.openssl_output.clear();
         self.config_output.clear();
         self.key_content.clear();
@@ -232,14 +392,14 @@ impl CertGenApp {
 
         self.advanced_mode = fake::rand::random_bool(0.5);
         let san_amount = fake::rand::rng().random::<u8>() % 5;
-        let mut san_list: Vec<String> = Vec::with_capacity(san_amount as usize + 1);
-        san_list.push(fake_domain.clone());
+        let mut san_list: Vec<SanEntry> = Vec::with_capacity(san_amount as usize + 1);
+        san_list.push(SanEntry::new(SanType::Dns, fake_domain.clone()));
         for _ in 0..san_amount {
             if fake::rand::random_bool(0.2) {
-                san_list.push(format!("{}", IP().fake::<String>()));
+                san_list.push(SanEntry::new(SanType::Ip, IP().fake::<String>()));
             } else {
                 let subdomain = sanitize(fake::faker::company::en::BsNoun().fake::<&str>()).to_ascii_lowercase();
-                san_list.push(format!("{}.{}", subdomain, fake_domain));
+                san_list.push(SanEntry::new(SanType::Dns, format!("{}.{}", subdomain, fake_domain)));
 
             }
         }
@@ -275,6 +435,9 @@ impl CertGenApp {
 
 impl eframe::App for CertGenApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // Validated once per frame, this rebuilds the config and checks the key file on disk
+        let issues = validation::validate_app(self);
+
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.vertical_centered(|ui| {
                 ui.heading("OpenSSL Certificate Request Generator");
@@ -284,7 +447,7 @@ impl eframe::App for CertGenApp {
 
             egui::ScrollArea::vertical().show(ui, |ui| {
                 // Form component
-                form::render(ui, self);
+                form::render(ui, self, &issues);
 
                 ui.add_space(10.0);
 
@@ -297,7 +460,10 @@ impl eframe::App for CertGenApp {
                         }
                     }
 
-                    if ui.button("Generate Configuration").clicked() {
+                    let has_errors = validation::has_errors(&issues);
+                    if ui.add_enabled(!has_errors, egui::Button::new("Generate Configuration"))
+                        .on_disabled_hover_text("Fix the fields marked in red first")
+                        .clicked() {
                         self.generate_config();
                     }
 
@@ -305,7 +471,7 @@ impl eframe::App for CertGenApp {
                     execute_button::render(ui, self);
 
                     // Save button component (only show if we have key and csr)
-                    if !self.key_content.is_empty() && !self.csr_content.is_empty() {
+                    if self.has_request_files() {
                         save_button::render(ui, self);
                     }
                 });
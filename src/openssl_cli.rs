@@ -1,22 +1,20 @@
 use std::io;
 use std::process::{Command, Stdio};
 
-pub fn execute_openssl_command(command: &str) -> io::Result<(String, String)> {
-    // Split the command into program and arguments
-    let parts: Vec<&str> = command.split_whitespace().collect();
-    if parts.is_empty() {
+/// Runs the `openssl` binary with the given arguments.
+/// Secrets are handed over through `envs` (e.g. `-passin env:NAME`) so they never show up in the process list.
+pub fn execute_openssl_command(args: &[String], envs: &[(&str, &str)]) -> io::Result<(String, String)> {
+    if args.is_empty() {
         return Err(io::Error::new(io::ErrorKind::InvalidInput, "Empty command"));
     }
 
-    let program = parts[0];
-    let args = &parts[1..];
-
     // Execute the command
-    let output = Command::new(program)
+    let output = Command::new("openssl")
         .args(args)
+        .envs(envs.iter().copied())
         .stdin(Stdio::inherit())
         .output()
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("Failed to execute command: {}", e)))?;
+        .map_err(|e| io::Error::other(format!("Failed to execute command: {}", e)))?;
 
     if output.status.success() {
         log::debug!("OpenSSL command executed successfully!");
@@ -24,5 +22,61 @@ pub fn execute_openssl_command(command: &str) -> io::Result<(String, String)> {
         log::error!("OpenSSL command failed with exit code: {}", output.status);
     }
 
-    Ok((String::from_utf8_lossy(&*output.stdout).parse().unwrap(), String::from_utf8_lossy(&*output.stderr).parse().unwrap()))
+    Ok((String::from_utf8_lossy(&output.stdout).parse().unwrap(), String::from_utf8_lossy(&output.stderr).parse().unwrap()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use crate::cert_config::SanEntry;
+    use crate::cert_config::tests::test_config;
+
+    /// Subject of a PEM request as OpenSSL prints it, non-ASCII characters unescaped
+    fn subject(dir: &std::path::Path, name: &str, csr_pem: &str) -> String {
+        let path = dir.join(name);
+        fs::write(&path, csr_pem).unwrap();
+        let args: Vec<String> = ["req", "-in", path.to_str().unwrap(), "-noout", "-subject", "-nameopt", "RFC2253,-esc_msb"]
+            .into_iter().map(String::from).collect();
+        execute_openssl_command(&args, &[]).unwrap().0.trim().to_string()
+    }
+
+    #[test]
+    fn test_non_ascii_common_name_round_trip() {
+        if execute_openssl_command(&["version".to_string()], &[]).is_err() {
+            eprintln!("openssl binary not found, skipping");
+            return;
+        }
+        let dir = std::env::temp_dir().join(format!("csr-generator-cli-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        // ascii mode transliterates O and L but keeps the CN as entered
+        let san = vec![SanEntry::detect("test.example.com")];
+        let mut config = test_config(&san);
+        config.key_algorithm = "ec";
+        config.common_name = "Bücher GmbH";
+        config.organization = "Müller AG";
+        let cnf = dir.join("request.cnf");
+        let csr = dir.join("request.csr");
+        fs::write(&cnf, config.generate_config().unwrap()).unwrap();
+        let args: Vec<String> = [
+            "req", "-new", "-config", cnf.to_str().unwrap(), "-newkey", "ec", "-pkeyopt", "ec_paramgen_curve:prime256v1",
+            "-keyout", dir.join("request.key").to_str().unwrap(), "-out", csr.to_str().unwrap(),
+        ].into_iter().map(String::from).collect();
+        let (_, stderr) = execute_openssl_command(&args, &[]).unwrap();
+        let cli_csr = fs::read_to_string(&csr).unwrap_or_else(|_| panic!("openssl req failed: {}", stderr));
+
+        let cli_subject = subject(&dir, "cli.csr", &cli_csr);
+        assert!(cli_subject.contains("CN=Bücher GmbH"), "{}", cli_subject);
+        assert!(cli_subject.contains("O=Mueller AG"), "{}", cli_subject);
+
+        // Both backends sign the same subject
+        #[cfg(feature = "openssl-native")]
+        {
+            let native = crate::openssl_native::generate_cert_request(&config).unwrap();
+            assert_eq!(subject(&dir, "native.csr", &native.csr_pem), cli_subject);
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
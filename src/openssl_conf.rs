@@ -0,0 +1,611 @@
+//! Typed model of OpenSSL config files, the format `openssl req -config` reads (NCONF).
+//! Text values are escaped when written, so form input can never turn into a comment,
+//! a variable or a section reference. The parser reads the same syntax back without expanding it.
+
+use std::fmt;
+
+/// Name of the section holding the entries before the first header
+pub const DEFAULT_SECTION: &str = "default";
+
+/// Variables may refer to variables, OpenSSL gives up on deep chains as well
+const MAX_EXPANSION_DEPTH: usize = 32;
+
+/// One piece of a value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fragment {
+    /// Literal text, escaped when written
+    Text(String),
+    /// `$name`, `$section::name` or `${section::name}`, `ENV` is the environment
+    Variable { section: Option<String>, name: String },
+    /// `@section` as the whole value or an element of a comma separated list, e.g. `subjectAltName = @alt_names`
+    SectionRef(String),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Value(pub Vec<Fragment>);
+
+impl Value {
+    /// Literal text, an empty text is the empty value
+    pub fn text(text: impl Into<String>) -> Self {
+        let text = text.into();
+        if text.is_empty() { Value::default() } else { Value(vec![Fragment::Text(text)]) }
+    }
+
+    pub fn variable(section: Option<&str>, name: &str) -> Self {
+        Value(vec![Fragment::Variable { section: section.map(str::to_string), name: name.to_string() }])
+    }
+
+    pub fn section_ref(name: &str) -> Self {
+        Value(vec![Fragment::SectionRef(name.to_string())])
+    }
+}
+
+impl From<&str> for Value {
+    fn from(text: &str) -> Self {
+        Value::text(text)
+    }
+}
+
+impl From<String> for Value {
+    fn from(text: String) -> Self {
+        Value::text(text)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Item {
+    Entry { key: String, value: Value },
+    Comment(String),
+    /// `.include path`, kept as written, the parser does not follow it
+    Include(String),
+    /// `.pragma` directive, e.g. `dollarid:on`
+    Pragma(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    pub name: String,
+    pub items: Vec<Item>,
+}
+
+impl Section {
+    pub fn new(name: impl Into<String>) -> Self {
+        Section { name: name.into(), items: Vec::new() }
+    }
+
+    pub fn entry(&mut self, key: impl Into<String>, value: impl Into<Value>) -> &mut Self {
+        self.items.push(Item::Entry { key: key.into(), value: value.into() });
+        self
+    }
+
+    pub fn comment(&mut self, text: impl Into<String>) -> &mut Self {
+        self.items.push(Item::Comment(text.into()));
+        self
+    }
+
+    /// The value of `key`, a repeated key overrides the earlier one like in OpenSSL
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        find_entry(&self.items, key)
+    }
+}
+
+fn find_entry<'a>(items: &'a [Item], key: &str) -> Option<&'a Value> {
+    items.iter().rev().find_map(|item| match item {
+        Item::Entry { key: k, value } if k == key => Some(value),
+        _ => None,
+    })
+}
+
+/// A whole config file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OpensslConf {
+    /// Items before the first section header, OpenSSL's "default" section
+    pub default: Vec<Item>,
+    pub sections: Vec<Section>,
+}
+
+impl OpensslConf {
+    /// The last section called `name`, appended when there is none yet
+    pub fn section(&mut self, name: &str) -> &mut Section {
+        match self.sections.iter().rposition(|section| section.name == name) {
+            Some(i) => &mut self.sections[i],
+            None => {
+                self.sections.push(Section::new(name));
+                self.sections.last_mut().unwrap()
+            }
+        }
+    }
+
+    /// The raw value of `key`, sections that appear more than once are merged
+    pub fn get(&self, section: &str, key: &str) -> Option<&Value> {
+        self.sections.iter().rev()
+            .filter(|s| s.name == section)
+            .find_map(|s| s.get(key))
+            .or_else(|| if section == DEFAULT_SECTION { find_entry(&self.default, key) } else { None })
+    }
+
+    /// The value with every variable resolved the way OpenSSL does: a variable without a section is
+    /// looked up in `section` first and then in the default section, `ENV` variables come from `env`
+    pub fn expand(&self, section: &str, value: &Value, env: &dyn Fn(&str) -> Option<String>) -> Result<String, String> {
+        self.expand_at(section, value, env, 0)
+    }
+
+    fn expand_at(&self, section: &str, value: &Value, env: &dyn Fn(&str) -> Option<String>, depth: usize) -> Result<String, String> {
+        if depth > MAX_EXPANSION_DEPTH {
+            return Err("Variables nested too deep".to_string());
+        }
+        let mut out = String::new();
+        for fragment in &value.0 {
+            match fragment {
+                Fragment::Text(text) => out.push_str(text),
+                Fragment::SectionRef(name) => {
+                    out.push('@');
+                    out.push_str(name);
+                }
+                Fragment::Variable { section: Some(env_section), name } if env_section == "ENV" => {
+                    out.push_str(&env(name).ok_or_else(|| format!("Environment variable {} is not set", name))?);
+                }
+                Fragment::Variable { section: target, name } => {
+                    let target = target.as_deref().unwrap_or(section);
+                    let (found_in, value) = match self.get(target, name) {
+                        Some(value) => (target, value),
+                        None => (DEFAULT_SECTION, self.get(DEFAULT_SECTION, name)
+                            .ok_or_else(|| format!("Variable {}::{} has no value", target, name))?),
+                    };
+                    out.push_str(&self.expand_at(found_in, value, env, depth + 1)?);
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    /// Variables without a value and `@section` references to missing sections.
+    /// Environment variables are only known when OpenSSL runs and count as set.
+    pub fn dangling_references(&self) -> Vec<String> {
+        let any_env = |_: &str| Some(String::new());
+        let sections = std::iter::once((DEFAULT_SECTION, self.default.as_slice()))
+            .chain(self.sections.iter().map(|section| (section.name.as_str(), section.items.as_slice())));
+        let mut problems = Vec::new();
+        for (section, items) in sections {
+            for item in items {
+                let Item::Entry { key, value } = item else {
+                    continue;
+                };
+                if let Err(err) = self.expand(section, value, &any_env) {
+                    problems.push(format!("{} in [{}]: {}", key, section, err));
+                }
+                for fragment in &value.0 {
+                    if let Fragment::SectionRef(name) = fragment && !self.sections.iter().any(|s| &s.name == name) {
+                        problems.push(format!("{} in [{}]: section {} does not exist", key, section, name));
+                    }
+                }
+            }
+        }
+        problems
+    }
+
+    /// Reads a config file, errors name the line
+    pub fn parse(text: &str) -> Result<OpensslConf, String> {
+        let mut conf = OpensslConf::default();
+        let mut current: Option<Section> = None;
+
+        for (number, line) in logical_lines(text) {
+            let line = line.trim_start_matches(is_ws);
+            let error = |message: String| format!("Line {}: {}", number, message);
+            if line.is_empty() {
+                continue;
+            }
+
+            let item = if let Some(comment) = line.strip_prefix('#') {
+                Item::Comment(comment.strip_prefix(' ').unwrap_or(comment).trim_end_matches(is_ws).to_string())
+            } else if let Some(rest) = line.strip_prefix('[') {
+                let (name, rest) = rest.split_once(']').ok_or_else(|| error("Missing ] after the section name".to_string()))?;
+                let name = name.trim_matches(is_ws);
+                if name.is_empty() || !name.chars().all(is_name_char) {
+                    return Err(error(format!("Invalid section name {}", name)));
+                }
+                if !strip_comment(rest).trim_matches(is_ws).is_empty() {
+                    return Err(error("Unexpected text after the section header".to_string()));
+                }
+                if let Some(section) = current.replace(Section::new(name)) {
+                    conf.sections.push(section);
+                }
+                continue;
+            } else if let Some(path) = directive(line, ".include") {
+                let value = parse_value(path).map_err(error)?;
+                match value.0.as_slice() {
+                    [] => return Err(error(".include without a path".to_string())),
+                    [Fragment::Text(path)] => Item::Include(path.clone()),
+                    _ => return Err(error("Variables in .include paths are not supported".to_string())),
+                }
+            } else if let Some(pragma) = directive(line, ".pragma") {
+                Item::Pragma(strip_comment(pragma).trim_matches(is_ws).to_string())
+            } else {
+                let key_len = line.find(|c: char| !is_name_char(c)).unwrap_or(line.len());
+                let (key, rest) = line.split_at(key_len);
+                let Some(value) = rest.trim_start_matches(is_ws).strip_prefix('=') else {
+                    return Err(error(format!("Expected key = value, found {}", line.trim_end_matches(is_ws))));
+                };
+                if key.is_empty() {
+                    return Err(error("Missing key before =".to_string()));
+                }
+                Item::Entry { key: key.to_string(), value: parse_value(value).map_err(error)? }
+            };
+
+            match current.as_mut() {
+                Some(section) => section.items.push(item),
+                None => conf.default.push(item),
+            }
+        }
+        conf.sections.extend(current);
+        Ok(conf)
+    }
+}
+
+fn is_ws(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\r')
+}
+
+/// Characters OpenSSL allows in keys and section names
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "_!.%&*+,/;?@^~|-".contains(c)
+}
+
+fn is_variable_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// `.include path` or `.include = path`
+fn directive<'a>(line: &'a str, name: &str) -> Option<&'a str> {
+    let rest = line.strip_prefix(name)?;
+    if let Some(rest) = rest.trim_start_matches(is_ws).strip_prefix('=') {
+        Some(rest)
+    } else {
+        rest.starts_with(is_ws).then_some(rest)
+    }
+}
+
+/// Lines with their 1-based number, a line ending in a single backslash continues on the next one
+fn logical_lines(text: &str) -> Vec<(usize, String)> {
+    let mut lines = Vec::new();
+    let mut pending: Option<(usize, String)> = None;
+    for (i, line) in text.lines().enumerate() {
+        let (number, mut joined) = pending.take().unwrap_or((i + 1, String::new()));
+        joined.push_str(line.trim_end_matches(is_ws));
+        if joined.ends_with('\\') && !joined.ends_with("\\\\") {
+            joined.pop();
+            pending = Some((number, joined));
+        } else {
+            lines.push((number, joined));
+        }
+    }
+    lines.extend(pending);
+    lines
+}
+
+/// Cuts a trailing `# comment`, quotes and escaped characters are skipped over
+fn strip_comment(text: &str) -> &str {
+    let mut quote = None;
+    let mut chars = text.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match (quote, c) {
+            (_, '\\') => {
+                chars.next();
+            }
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if c == q => quote = None,
+            (None, '#') => return &text[..i],
+            _ => {}
+        }
+    }
+    text
+}
+
+fn parse_value(text: &str) -> Result<Value, String> {
+    let text = strip_comment(text).trim_start_matches(is_ws);
+    // Trailing whitespace is dropped unless it is escaped
+    let mut end = text.trim_end_matches(is_ws).len();
+    if end < text.len() && text[..end].ends_with('\\') && !text[..end].ends_with("\\\\") {
+        end += 1;
+    }
+    let mut chars = text[..end].chars().peekable();
+
+    let mut fragments = Vec::new();
+    let mut literal = String::new();
+    // At the start of the value or of a list element, where @section refers to a section
+    let mut list_start = true;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                if let Some(escaped) = chars.next() {
+                    literal.push(match escaped {
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        'b' => '\u{8}',
+                        other => other,
+                    });
+                }
+                list_start = false;
+            }
+            '"' | '\'' => {
+                while let Some(quoted) = chars.next() {
+                    match quoted {
+                        _ if quoted == c => break,
+                        '\\' => literal.extend(chars.next()),
+                        _ => literal.push(quoted),
+                    }
+                }
+                list_start = false;
+            }
+            '$' => {
+                let close = match chars.peek() {
+                    Some('{') => Some('}'),
+                    Some('(') => Some(')'),
+                    _ => None,
+                };
+                let reference: String = match close {
+                    Some(close) => {
+                        chars.next();
+                        let mut reference = String::new();
+                        loop {
+                            match chars.next() {
+                                Some(c) if c == close => break,
+                                Some(c) => reference.push(c),
+                                None => return Err(format!("Missing {} after the variable {}", close, reference)),
+                            }
+                        }
+                        reference
+                    }
+                    None => {
+                        let mut reference = String::new();
+                        while let Some(&c) = chars.peek() {
+                            if is_variable_char(c) {
+                                reference.push(c);
+                                chars.next();
+                            } else if c == ':' && !reference.is_empty() && !reference.contains("::") {
+                                // section::name, only a double colon belongs to the reference
+                                let mut ahead = chars.clone();
+                                ahead.next();
+                                if ahead.next() != Some(':') {
+                                    break;
+                                }
+                                reference.push_str("::");
+                                chars.next();
+                                chars.next();
+                            } else {
+                                break;
+                            }
+                        }
+                        reference
+                    }
+                };
+                let (section, name) = match reference.split_once("::") {
+                    Some((section, name)) => (Some(section.to_string()), name.to_string()),
+                    None => (None, reference.clone()),
+                };
+                if name.is_empty() || !name.chars().all(is_variable_char)
+                    || section.as_deref().is_some_and(|section| section.is_empty() || !section.chars().all(is_variable_char)) {
+                    return Err(format!("Invalid variable ${}", reference));
+                }
+                flush(&mut fragments, &mut literal);
+                fragments.push(Fragment::Variable { section, name });
+                list_start = false;
+            }
+            '@' if list_start && chars.peek().is_some_and(|c| is_name_char(*c) && *c != ',') => {
+                let mut name = String::new();
+                while let Some(&c) = chars.peek() && is_name_char(c) && c != ',' {
+                    name.push(c);
+                    chars.next();
+                }
+                flush(&mut fragments, &mut literal);
+                fragments.push(Fragment::SectionRef(name));
+                list_start = false;
+            }
+            ',' => {
+                literal.push(c);
+                list_start = true;
+            }
+            ' ' | '\t' => literal.push(c),
+            _ => {
+                literal.push(c);
+                list_start = false;
+            }
+        }
+    }
+    flush(&mut fragments, &mut literal);
+    Ok(Value(fragments))
+}
+
+fn flush(fragments: &mut Vec<Fragment>, literal: &mut String) {
+    if !literal.is_empty() {
+        fragments.push(Fragment::Text(std::mem::take(literal)));
+    }
+}
+
+/// Escapes literal text, `list_start` tells whether an `@` here would be read as a section reference
+fn write_text(f: &mut fmt::Formatter<'_>, text: &str, value_start: bool, value_end: bool, list_start: &mut bool) -> fmt::Result {
+    // Trailing spaces are trimmed by OpenSSL even when escaped, quotes keep them
+    let body_len = if value_end { text.trim_end_matches(' ').len() } else { text.len() };
+    for (i, c) in text[..body_len].char_indices() {
+        match c {
+            '\\' | '$' | '#' | '"' | '\'' => write!(f, "\\{}", c)?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            '\u{8}' => f.write_str("\\b")?,
+            '@' if *list_start => f.write_str("\\@")?,
+            ' ' if value_start && i == 0 => f.write_str("\\ ")?,
+            _ => write!(f, "{}", c)?,
+        }
+        match c {
+            ',' => *list_start = true,
+            ' ' if !(value_start && i == 0) => {}
+            _ => *list_start = false,
+        }
+    }
+    if body_len < text.len() {
+        write!(f, "\"{}\"", &text[body_len..])?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut list_start = true;
+        for (i, fragment) in self.0.iter().enumerate() {
+            match fragment {
+                Fragment::Text(text) => write_text(f, text, i == 0, i + 1 == self.0.len(), &mut list_start)?,
+                Fragment::Variable { section, name } => {
+                    let reference = match section {
+                        Some(section) => format!("{}::{}", section, name),
+                        None => name.clone(),
+                    };
+                    // Braces when the following text would otherwise continue the name
+                    let continues = matches!(self.0.get(i + 1), Some(Fragment::Text(next)) if next.starts_with(|c: char| is_variable_char(c) || c == ':'));
+                    if continues {
+                        write!(f, "${{{}}}", reference)?;
+                    } else {
+                        write!(f, "${}", reference)?;
+                    }
+                    list_start = false;
+                }
+                Fragment::SectionRef(name) => {
+                    write!(f, "@{}", name)?;
+                    list_start = false;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Item::Entry { key, value } => writeln!(f, "{} = {}", key, value),
+            Item::Comment(text) if text.is_empty() => writeln!(f, "#"),
+            Item::Comment(text) => text.lines().try_for_each(|line| writeln!(f, "# {}", line)),
+            Item::Include(path) => writeln!(f, ".include {}", Value::text(path.as_str())),
+            Item::Pragma(pragma) => writeln!(f, ".pragma {}", pragma),
+        }
+    }
+}
+
+impl fmt::Display for OpensslConf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for item in &self.default {
+            write!(f, "{}", item)?;
+        }
+        // Sections are separated by a blank line
+        for (i, section) in self.sections.iter().enumerate() {
+            if i > 0 || !self.default.is_empty() {
+                writeln!(f)?;
+            }
+            writeln!(f, "[{}]", section.name)?;
+            for item in &section.items {
+                write!(f, "{}", item)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SYNTAX: &str = include_str!("../tests/golden/syntax.cnf");
+
+    fn text(conf: &OpensslConf, section: &str, key: &str) -> String {
+        let env = |name: &str| (name == "MAIL").then(|| "pki@example.com".to_string());
+        conf.expand(section, conf.get(section, key).unwrap(), &env).unwrap()
+    }
+
+    #[test]
+    fn test_parse_syntax() {
+        let conf = OpensslConf::parse(SYNTAX).unwrap();
+        assert_eq!(conf.default, [
+            Item::Comment("Written by hand, everything the parser understands".to_string()),
+            Item::Entry { key: "HOME".to_string(), value: Value::text("/srv/pki") },
+            Item::Include("/etc/ssl/common.cnf".to_string()),
+            Item::Pragma("dollarid:on".to_string()),
+        ]);
+        let names: Vec<&str> = conf.sections.iter().map(|section| section.name.as_str()).collect();
+        assert_eq!(names, ["req", "dn", "ext", "alt", "policy"]);
+
+        assert_eq!(text(&conf, "req", "distinguished_name"), "dn");
+        assert_eq!(text(&conf, "req", "default_keyfile"), "/srv/pki/key.pem");
+        assert_eq!(text(&conf, "req", "output"), "/srv/pki/req.pem");
+        assert_eq!(text(&conf, "req", "prompt"), "no");
+        assert_eq!(text(&conf, "dn", "O"), "Quoted  # not a comment");
+        assert_eq!(text(&conf, "dn", "OU"), "single and \"escaped\"");
+        assert_eq!(text(&conf, "dn", "CN"), "www.example.com");
+        assert_eq!(text(&conf, "dn", "L"), "line\nbreak\ttab");
+        assert_eq!(text(&conf, "dn", "emailAddress"), "pki@example.com");
+
+        assert_eq!(conf.get("ext", "subjectAltName"), Some(&Value(vec![
+            Fragment::Text("DNS:example.com, ".to_string()),
+            Fragment::SectionRef("alt".to_string()),
+        ])));
+        assert_eq!(conf.get("ext", "certificatePolicies"), Some(&Value(vec![
+            Fragment::Text("ia5org,".to_string()),
+            Fragment::SectionRef("policy".to_string()),
+        ])));
+        assert!(conf.dangling_references().is_empty());
+
+        // Written back in canonical form, it reads as the same model
+        let written = conf.to_string();
+        assert_eq!(OpensslConf::parse(&written).unwrap(), conf);
+        assert!(written.starts_with("# Written by hand, everything the parser understands\nHOME = /srv/pki\n.include /etc/ssl/common.cnf\n.pragma dollarid:on\n\n[req]\n"));
+    }
+
+    #[test]
+    fn test_escaping_round_trip() {
+        let values = [
+            "plain", "O'Reilly \"Books\"", "$HOME and ${x}", "# not a comment", r"C:\Users\", " leading", "trailing  ",
+            "  both  ", "@not_a_section", "a, @b", "mail@example.com", "line\nbreak\r\ttab", "   ", "ünïcödé",
+        ];
+        for value in values {
+            let mut conf = OpensslConf::default();
+            conf.section("test").entry("key", value);
+            let written = conf.to_string();
+            let read = OpensslConf::parse(&written).unwrap_or_else(|err| panic!("{:?}: {}", value, err));
+            assert_eq!(read, conf, "{:?} written as {:?}", value, written);
+            assert_eq!(text(&read, "test", "key"), value);
+        }
+
+        let mut conf = OpensslConf::default();
+        conf.section("test")
+            .entry("variable", Value(vec![Fragment::Variable { section: None, name: "a".to_string() }, Fragment::Text("_b".to_string())]))
+            .entry("a", "1");
+        assert_eq!(conf.to_string(), "[test]\nvariable = ${a}_b\na = 1\n");
+        assert_eq!(text(&conf, "test", "variable"), "1_b");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(OpensslConf::parse("[req]\nno equals sign\n"), Err("Line 2: Expected key = value, found no equals sign".to_string()));
+        assert_eq!(OpensslConf::parse("[req\n"), Err("Line 1: Missing ] after the section name".to_string()));
+        assert_eq!(OpensslConf::parse("a = ${b\n"), Err("Line 1: Missing } after the variable b".to_string()));
+        assert_eq!(OpensslConf::parse("a = $\n"), Err("Line 1: Invalid variable $".to_string()));
+    }
+
+    #[test]
+    fn test_dangling_references() {
+        let mut conf = OpensslConf::default();
+        conf.section("req")
+            .entry("subjectAltName", Value::section_ref("alt_names"))
+            .entry("password", Value::variable(Some("ENV"), "PASSWORD"))
+            .entry("keyfile", Value::variable(None, "dir"));
+        assert_eq!(conf.dangling_references(), [
+            "subjectAltName in [req]: section alt_names does not exist",
+            "keyfile in [req]: Variable req::dir has no value",
+        ]);
+
+        // Variables fall back to the default section
+        conf.default.push(Item::Entry { key: "dir".to_string(), value: Value::text("/tmp") });
+        conf.section("alt_names").entry("DNS.1", "example.com");
+        assert!(conf.dangling_references().is_empty());
+    }
+}
@@ -0,0 +1,87 @@
+/// Rough strength rating of a key passphrase, used for the meter in the form
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Strength {
+    VeryWeak,
+    Weak,
+    Reasonable,
+    Strong,
+    VeryStrong,
+}
+
+impl Strength {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Strength::VeryWeak => "Very weak",
+            Strength::Weak => "Weak",
+            Strength::Reasonable => "Reasonable",
+            Strength::Strong => "Strong",
+            Strength::VeryStrong => "Very strong",
+        }
+    }
+
+    /// Fill level of the meter between 0.0 and 1.0
+    pub fn fraction(&self) -> f32 {
+        (*self as u8 + 1) as f32 / 5.0
+    }
+}
+
+/// Minimum length OpenSSL accepts for PEM passphrases
+pub const MIN_PASSPHRASE_LEN: usize = 4;
+
+/// Estimates the entropy of a passphrase from its length and the character classes used.
+/// Repeated characters (e.g. "aaaa") only count once per run.
+pub fn estimate_bits(passphrase: &str) -> f64 {
+    let (mut lower, mut upper, mut digit, mut symbol, mut other) = (false, false, false, false, false);
+    let mut effective_len = 0usize;
+    let mut previous = None;
+
+    for c in passphrase.chars() {
+        match c {
+            'a'..='z' => lower = true,
+            'A'..='Z' => upper = true,
+            '0'..='9' => digit = true,
+            c if c.is_ascii() => symbol = true,
+            _ => other = true,
+        }
+        if previous != Some(c) {
+            effective_len += 1;
+        }
+        previous = Some(c);
+    }
+
+    let pool = [(lower, 26), (upper, 26), (digit, 10), (symbol, 33), (other, 100)]
+        .iter()
+        .filter(|(used, _)| *used)
+        .map(|(_, size)| size)
+        .sum::<u32>();
+
+    if pool == 0 {
+        return 0.0;
+    }
+    effective_len as f64 * f64::from(pool).log2()
+}
+
+pub fn strength(passphrase: &str) -> Strength {
+    match estimate_bits(passphrase) {
+        bits if bits < 28.0 => Strength::VeryWeak,
+        bits if bits < 36.0 => Strength::Weak,
+        bits if bits < 60.0 => Strength::Reasonable,
+        bits if bits < 128.0 => Strength::Strong,
+        _ => Strength::VeryStrong,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strength_ratings() {
+        assert_eq!(strength(""), Strength::VeryWeak);
+        assert_eq!(strength("abcd"), Strength::VeryWeak);
+        assert_eq!(strength("aaaaaaaaaaaaaaaaaaaa"), Strength::VeryWeak);
+        assert_eq!(strength("Tr0ub4dor"), Strength::Reasonable);
+        assert_eq!(strength("password"), Strength::Weak);
+        assert_eq!(strength("correct horse battery staple"), Strength::VeryStrong);
+    }
+}
@@ -0,0 +1,124 @@
+//! Public Suffix List lookups against the bundled snapshot in `assets/public_suffix_list.dat`
+//! (https://publicsuffix.org/list/). Like the Baseline Requirements (3.2.2.6) only the ICANN section is used.
+//!
+//! The file carries no version header, so the snapshot is recorded in the line below.
+//! `scripts/update_public_suffix_list.sh` refreshes the file and this line (see the README).
+//! Snapshot: upstream list of 2023-02-09 (Debian publicsuffix 20230209.2326-1)
+
+use std::collections::HashSet;
+use std::sync::OnceLock;
+use crate::cert_config::dns_to_ascii;
+
+const LIST: &str = include_str!("../assets/public_suffix_list.dat");
+
+/// Registrable domains a request may span before a warning, each needs its own domain validation
+pub const REGISTRABLE_DOMAIN_WARNING_THRESHOLD: usize = 3;
+
+#[derive(Default)]
+struct Rules {
+    /// `co.uk`
+    exact: HashSet<String>,
+    /// `*.ck`, stored without the leading `*.`
+    wildcard: HashSet<String>,
+    /// `!www.ck`, stored without the leading `!`
+    exception: HashSet<String>,
+}
+
+fn rules() -> &'static Rules {
+    static RULES: OnceLock<Rules> = OnceLock::new();
+    RULES.get_or_init(|| {
+        let mut rules = Rules::default();
+        let icann = LIST.lines()
+            .skip_while(|line| !line.contains("===BEGIN ICANN DOMAINS==="))
+            .take_while(|line| !line.contains("===END ICANN DOMAINS==="));
+        for line in icann {
+            // A rule ends at the first whitespace, everything from // on is a comment
+            let Some(rule) = line.split_whitespace().next().filter(|rule| !rule.starts_with("//")) else {
+                continue;
+            };
+            // Some rules are listed in Unicode, names are compared as A-labels
+            let (set, rule) = if let Some(rest) = rule.strip_prefix("*.") {
+                (&mut rules.wildcard, rest)
+            } else if let Some(rest) = rule.strip_prefix('!') {
+                (&mut rules.exception, rest)
+            } else {
+                (&mut rules.exact, rule)
+            };
+            if let Ok(ascii) = dns_to_ascii(rule) {
+                set.insert(ascii.to_ascii_lowercase());
+            }
+        }
+        rules
+    })
+}
+
+/// Number of trailing labels that form the public suffix, `None` when no listed rule matches
+/// (the list's implicit `*` rule would then make the last label the suffix)
+fn listed_suffix_labels(labels: &[&str]) -> Option<usize> {
+    let rules = rules();
+    // The first match from the left is the longest, exceptions outrank the wildcard they carve out of
+    for i in 0..labels.len() {
+        let candidate = labels[i..].join(".");
+        if rules.exception.contains(&candidate) {
+            return Some(labels.len() - i - 1);
+        }
+        if rules.exact.contains(&candidate) || (i + 1 < labels.len() && rules.wildcard.contains(&labels[i + 1..].join("."))) {
+            return Some(labels.len() - i);
+        }
+    }
+    None
+}
+
+fn labels(name: &str) -> Vec<String> {
+    let ascii = dns_to_ascii(name).unwrap_or_else(|_| name.to_string());
+    ascii.trim_end_matches('.').to_ascii_lowercase().split('.').map(str::to_string).collect()
+}
+
+/// Whether the name itself is listed as a public suffix, e.g. `co.uk` or `com`
+pub fn is_public_suffix(name: &str) -> bool {
+    let labels = labels(name);
+    let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+    listed_suffix_labels(&labels) == Some(labels.len())
+}
+
+/// The public suffix plus one label, e.g. `example.co.uk` for `www.example.co.uk`.
+/// `None` for public suffixes themselves and for names under an unlisted top-level label like `intranet`.
+pub fn registrable_domain(name: &str) -> Option<String> {
+    let labels = labels(name.strip_prefix("*.").unwrap_or(name));
+    let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+    let suffix = listed_suffix_labels(&labels)?;
+    (suffix < labels.len()).then(|| labels[labels.len() - suffix - 1..].join("."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_public_suffixes() {
+        assert!(is_public_suffix("com"));
+        assert!(is_public_suffix("co.uk"));
+        assert!(is_public_suffix("CO.UK."));
+        assert!(!is_public_suffix("example.co.uk"));
+        // Private section entries are not used
+        assert!(!is_public_suffix("github.io"));
+        // Unlisted names are not reported, only listed rules count
+        assert!(!is_public_suffix("intranet"));
+    }
+
+    #[test]
+    fn test_registrable_domain() {
+        assert_eq!(registrable_domain("www.example.co.uk").as_deref(), Some("example.co.uk"));
+        assert_eq!(registrable_domain("*.example.com").as_deref(), Some("example.com"));
+        assert_eq!(registrable_domain("example.com").as_deref(), Some("example.com"));
+        assert_eq!(registrable_domain("co.uk"), None);
+        assert_eq!(registrable_domain("host.intranet"), None);
+
+        // Wildcard and exception rules: *.ck with !www.ck
+        assert_eq!(registrable_domain("a.b.ck").as_deref(), Some("a.b.ck"));
+        assert_eq!(registrable_domain("www.ck").as_deref(), Some("www.ck"));
+
+        // Unicode rules match A-labels
+        assert_eq!(registrable_domain("www.bücher.de").as_deref(), Some("xn--bcher-kva.de"));
+    }
+}
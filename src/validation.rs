@@ -0,0 +1,561 @@
+//! Collects every problem of a request at once, each tied to the form input it belongs to
+
+use std::io;
+use crate::CertGenApp;
+use crate::cert_config::{
+    CertConfig, EC_CURVES, EXTENDED_KEY_USAGES, JURISDICTION_COUNTRY_OID, JURISDICTION_LOCALITY_OID,
+    JURISDICTION_STATE_OID, KEY_USAGES, STRING_ENCODINGS, SanRedundancy, SanType, UB_PKCS9_STRING, check_subject_entry,
+    is_valid_oid, is_valid_organization_identifier, san_redundancies, sanitize_for_cert_field, validate_ev_subject,
+};
+use crate::countries;
+use crate::passphrase;
+use crate::public_suffix;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// Form input a problem belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldId {
+    Country,
+    State,
+    Locality,
+    Organization,
+    CommonName,
+    OrganizationalUnit,
+    Email,
+    StreetAddress,
+    PostalCode,
+    OrganizationIdentifier,
+    BusinessCategory,
+    Title,
+    GivenName,
+    Surname,
+    Pseudonym,
+    SerialNumber,
+    DomainComponent,
+    JurisdictionCountry,
+    JurisdictionState,
+    JurisdictionLocality,
+    /// The custom subject editor as a whole
+    SubjectList,
+    /// Row of the custom subject editor
+    Subject(usize),
+    San(usize),
+    /// The SAN list as a whole
+    SanList,
+    RequestProfile,
+    CaPathLen,
+    StringEncoding,
+    KeyAlgorithm,
+    SignatureScheme,
+    KeyUsage,
+    ExtendedKeyUsage,
+    CustomExtension(usize),
+    ChallengePassword,
+    UnstructuredName,
+    AdcsTemplate,
+    ExistingKey,
+    KeyPassphrase,
+}
+
+impl FieldId {
+    /// Fixed form field that holds a subject attribute
+    pub fn for_attribute(attribute: &str) -> Option<FieldId> {
+        Some(match attribute {
+            "C" => FieldId::Country,
+            "ST" => FieldId::State,
+            "L" => FieldId::Locality,
+            "O" => FieldId::Organization,
+            "CN" => FieldId::CommonName,
+            "OU" => FieldId::OrganizationalUnit,
+            "emailAddress" => FieldId::Email,
+            "street" => FieldId::StreetAddress,
+            "postalCode" => FieldId::PostalCode,
+            "organizationIdentifier" => FieldId::OrganizationIdentifier,
+            "businessCategory" => FieldId::BusinessCategory,
+            "title" => FieldId::Title,
+            "GN" => FieldId::GivenName,
+            "SN" => FieldId::Surname,
+            "pseudonym" => FieldId::Pseudonym,
+            "serialNumber" => FieldId::SerialNumber,
+            "DC" => FieldId::DomainComponent,
+            JURISDICTION_COUNTRY_OID => FieldId::JurisdictionCountry,
+            JURISDICTION_STATE_OID => FieldId::JurisdictionState,
+            JURISDICTION_LOCALITY_OID => FieldId::JurisdictionLocality,
+            _ => return None,
+        })
+    }
+
+    /// Name of the field as the form shows it
+    pub fn label(&self) -> String {
+        match self {
+            FieldId::Country => "Country".to_string(),
+            FieldId::State => "State/Province".to_string(),
+            FieldId::Locality => "Locality (city)".to_string(),
+            FieldId::Organization => "Organization".to_string(),
+            FieldId::CommonName => "Common Name".to_string(),
+            FieldId::OrganizationalUnit => "Organizational Unit".to_string(),
+            FieldId::Email => "Email Address".to_string(),
+            FieldId::StreetAddress => "Street Address".to_string(),
+            FieldId::PostalCode => "Postal Code".to_string(),
+            FieldId::OrganizationIdentifier => "Organization Identifier".to_string(),
+            FieldId::BusinessCategory => "Business Category".to_string(),
+            FieldId::Title => "Title".to_string(),
+            FieldId::GivenName => "Given Name".to_string(),
+            FieldId::Surname => "Surname".to_string(),
+            FieldId::Pseudonym => "Pseudonym".to_string(),
+            FieldId::SerialNumber => "Serial Number".to_string(),
+            FieldId::DomainComponent => "Domain Component".to_string(),
+            FieldId::JurisdictionCountry => "Jurisdiction Country".to_string(),
+            FieldId::JurisdictionState => "Jurisdiction State/Province".to_string(),
+            FieldId::JurisdictionLocality => "Jurisdiction Locality".to_string(),
+            FieldId::SubjectList => "Subject".to_string(),
+            FieldId::Subject(i) => format!("Subject entry {}", i + 1),
+            FieldId::San(i) => format!("SAN entry {}", i + 1),
+            FieldId::SanList => "Subject Alternative Names".to_string(),
+            FieldId::RequestProfile => "Request Profile".to_string(),
+            FieldId::CaPathLen => "Path Length".to_string(),
+            FieldId::StringEncoding => "Subject Encoding".to_string(),
+            FieldId::KeyAlgorithm => "Key Algorithm".to_string(),
+            FieldId::SignatureScheme => "Signature Scheme".to_string(),
+            FieldId::KeyUsage => "Key Usage".to_string(),
+            FieldId::ExtendedKeyUsage => "Extended Key Usage".to_string(),
+            FieldId::CustomExtension(i) => format!("Custom extension {}", i + 1),
+            FieldId::ChallengePassword => "Challenge Password".to_string(),
+            FieldId::UnstructuredName => "Unstructured Name".to_string(),
+            FieldId::AdcsTemplate => "ADCS Template".to_string(),
+            FieldId::ExistingKey => "Private Key File".to_string(),
+            FieldId::KeyPassphrase => "Key Passphrase".to_string(),
+        }
+    }
+
+    /// Message for a required field left empty
+    fn required_message(&self) -> String {
+        match self {
+            FieldId::Country => "Country code must be exactly 2 letters".to_string(),
+            FieldId::Subject(i) => format!("Subject entry {} is empty", i + 1),
+            other => format!("{} is required", other.label()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Issue {
+    pub severity: Severity,
+    pub field: FieldId,
+    pub message: String,
+}
+
+impl Issue {
+    pub fn error(field: FieldId, message: impl Into<String>) -> Self {
+        Issue { severity: Severity::Error, field, message: message.into() }
+    }
+
+    pub fn warning(field: FieldId, message: impl Into<String>) -> Self {
+        Issue { severity: Severity::Warning, field, message: message.into() }
+    }
+}
+
+pub fn has_errors(issues: &[Issue]) -> bool {
+    issues.iter().any(|issue| issue.severity == Severity::Error)
+}
+
+/// Problems of one input, the most severe first
+pub fn field_issues(issues: &[Issue], field: FieldId) -> Vec<&Issue> {
+    let mut found: Vec<&Issue> = issues.iter().filter(|issue| issue.field == field).collect();
+    found.sort_by_key(|issue| std::cmp::Reverse(issue.severity));
+    found
+}
+
+/// Every error of the config as one `InvalidInput` error, warnings do not block a request.
+/// Both backends call this before they write anything.
+pub fn ensure_valid(config: &CertConfig) -> io::Result<()> {
+    let errors: Vec<String> = validate_config(config).into_iter()
+        .filter(|issue| issue.severity == Severity::Error)
+        .map(|issue| issue.message)
+        .collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(io::Error::new(io::ErrorKind::InvalidInput, errors.join("\n")))
+    }
+}
+
+/// Checks everything the generated request depends on
+pub fn validate_config(config: &CertConfig) -> Vec<Issue> {
+    let mut issues = Vec::new();
+
+    if !STRING_ENCODINGS.iter().any(|(mode, _)| *mode == config.string_encoding) {
+        issues.push(Issue::error(FieldId::StringEncoding, format!("Unknown string encoding: {}", config.string_encoding)));
+    }
+
+    validate_subject(config, &mut issues);
+
+    match config.key_algorithm {
+        "rsa" | "ed25519" | "ed448" => {}
+        "ec" => {
+            if !EC_CURVES.iter().any(|(curve, _)| *curve == config.ec_curve) {
+                issues.push(Issue::error(FieldId::KeyAlgorithm, format!("Unsupported curve: {}", config.ec_curve)));
+            }
+        }
+        other => issues.push(Issue::error(FieldId::KeyAlgorithm, format!("Unsupported key algorithm: {}", other))),
+    }
+
+    match config.signature_scheme {
+        "pkcs1" => {}
+        "pss" => {
+            if config.existing_key.is_none() && config.key_algorithm != "rsa" {
+                issues.push(Issue::error(FieldId::SignatureScheme, "RSA-PSS signatures require an RSA key"));
+            }
+            if !matches!(config.pss_salt_length, "digest" | "max") && config.pss_salt_length.parse::<u32>().is_err() {
+                issues.push(Issue::error(FieldId::SignatureScheme, format!("Invalid PSS salt length: {}", config.pss_salt_length)));
+            }
+        }
+        other => issues.push(Issue::error(FieldId::SignatureScheme, format!("Unsupported signature scheme: {}", other))),
+    }
+
+    match config.request_profile {
+        "end_entity" | "ev" => {}
+        "ca" => {
+            if !config.ca_path_len.is_empty() && config.ca_path_len.parse::<u32>().is_err() {
+                issues.push(Issue::error(FieldId::CaPathLen, format!("Invalid path length: {}", config.ca_path_len)));
+            }
+        }
+        other => issues.push(Issue::error(FieldId::RequestProfile, format!("Unknown request profile: {}", other))),
+    }
+
+    for (i, san) in config.san.iter().enumerate() {
+        if let Err(err) = san.validate() {
+            issues.push(Issue::error(FieldId::San(i), err));
+        } else if san.san_type == SanType::Dns {
+            // Baseline Requirements 3.2.2.6, nobody can prove control over a whole public suffix
+            if let Some(base) = san.value.strip_prefix("*.") && public_suffix::is_public_suffix(base) {
+                issues.push(Issue::error(FieldId::San(i), format!("Wildcard {} is directly under the public suffix {}", san.value, base)));
+            } else if public_suffix::is_public_suffix(&san.value) {
+                issues.push(Issue::error(FieldId::San(i), format!("{} is a public suffix, not a domain of its own", san.value)));
+            }
+        }
+    }
+
+    for (i, redundancy) in san_redundancies(config.san) {
+        let message = match redundancy {
+            SanRedundancy::Duplicate(first) => format!("Same as {} after normalization, it is written once", config.san[first].value),
+            SanRedundancy::CoveredBy(wildcard) => format!("Already matched by the wildcard {}", config.san[wildcard].value),
+        };
+        issues.push(Issue::warning(FieldId::San(i), message));
+    }
+
+    let mut domains: Vec<String> = config.san.iter()
+        .filter(|san| san.san_type == SanType::Dns)
+        .filter_map(|san| public_suffix::registrable_domain(&san.value))
+        .collect();
+    domains.sort();
+    domains.dedup();
+    if domains.len() > public_suffix::REGISTRABLE_DOMAIN_WARNING_THRESHOLD {
+        issues.push(Issue::warning(FieldId::SanList, format!(
+            "The SANs span {} registrable domains ({}), the CA validates each of them separately", domains.len(), domains.join(", "))));
+    }
+
+    if config.is_ev_request() {
+        // EV Guidelines 9.8.1: no wildcards, only domain names
+        if config.common_name.starts_with("*.") {
+            issues.push(Issue::error(FieldId::CommonName, "Wildcard names are not allowed in EV requests"));
+        }
+        for (i, san) in config.san.iter().enumerate() {
+            if san.value.starts_with("*.") {
+                issues.push(Issue::error(FieldId::San(i), "Wildcard names are not allowed in EV requests"));
+            } else if san.san_type != SanType::Dns {
+                issues.push(Issue::error(FieldId::San(i), format!("EV requests may only contain DNS names, not {}", san)));
+            }
+        }
+    }
+
+    for usage in config.key_usage {
+        if !KEY_USAGES.iter().any(|(name, _)| name == usage) {
+            issues.push(Issue::error(FieldId::KeyUsage, format!("Unknown key usage: {}", usage)));
+        }
+    }
+    for usage in config.extended_key_usage {
+        if !EXTENDED_KEY_USAGES.iter().any(|(name, _)| name == usage) && !is_valid_oid(usage) {
+            issues.push(Issue::error(FieldId::ExtendedKeyUsage, format!("Extended key usage is neither known nor an OID: {}", usage)));
+        }
+    }
+
+    for (field, name, value) in [
+        (FieldId::ChallengePassword, "challengePassword", config.challenge_password),
+        (FieldId::UnstructuredName, "unstructuredName", config.unstructured_name),
+    ] {
+        if let Some(value) = value && (value.is_empty() || value.chars().count() > UB_PKCS9_STRING || value.contains('\n')) {
+            issues.push(Issue::error(field, format!("{} must be a single line of 1 to {} characters", name, UB_PKCS9_STRING)));
+        }
+    }
+
+    if let Some(template) = config.adcs_template {
+        if template.contains('\n') {
+            issues.push(Issue::error(FieldId::AdcsTemplate, "Certificate template must be a single line"));
+        }
+        for (name, version) in [("major", config.adcs_template_major_version), ("minor", config.adcs_template_minor_version)] {
+            if !version.is_empty() && version.parse::<u32>().is_err() {
+                issues.push(Issue::error(FieldId::AdcsTemplate, format!("Invalid template {} version: {}", name, version)));
+            }
+        }
+        if config.adcs_template_major_version.is_empty() && !config.adcs_template_minor_version.is_empty() {
+            issues.push(Issue::error(FieldId::AdcsTemplate, "Template minor version requires a major version"));
+        }
+    }
+
+    for (i, extension) in config.custom_extensions.iter().enumerate() {
+        if let Err(err) = extension.validate() {
+            issues.push(Issue::error(FieldId::CustomExtension(i), err));
+        } else if config.custom_extensions[..i].iter().any(|other| other.oid.trim() == extension.oid.trim()) {
+            issues.push(Issue::error(FieldId::CustomExtension(i), format!("Extension {} is listed twice", extension.oid.trim())));
+        }
+    }
+
+    issues
+}
+
+/// Subject checks, at most one problem per attribute
+fn validate_subject(config: &CertConfig, issues: &mut Vec<Issue>) {
+    let subject = config.subject_entries();
+    let first_cn = subject.iter().position(|entry| entry.attribute == "CN");
+    let field_of = |i: usize, attribute: &str| match config.custom_subject {
+        Some(_) if Some(i) == first_cn => FieldId::CommonName,
+        Some(_) => FieldId::Subject(i),
+        None => FieldId::for_attribute(attribute).unwrap_or(FieldId::SubjectList),
+    };
+
+    if let Some(custom) = config.custom_subject {
+        if first_cn.is_none() {
+            issues.push(Issue::error(FieldId::SubjectList, "Custom subject needs a CN entry"));
+        }
+        if custom.first().is_some_and(|entry| entry.multi_valued) {
+            issues.push(Issue::error(FieldId::Subject(0), "The first subject entry cannot be joined with a previous one"));
+        }
+    }
+    if config.common_name.trim().is_empty() {
+        issues.push(Issue::error(FieldId::CommonName, FieldId::CommonName.required_message()));
+    }
+
+    for (i, entry) in subject.iter().enumerate() {
+        let field = field_of(i, &entry.attribute);
+        let problem = if entry.value.trim().is_empty() {
+            // An empty CN is already reported above
+            (field != FieldId::CommonName).then(|| field.required_message())
+        } else if let Err(err) = entry.validate() {
+            Some(err)
+        } else if matches!(entry.attribute.as_str(), "C" | JURISDICTION_COUNTRY_OID) && !countries::is_known_country(&entry.value) {
+            Some(if entry.value.len() == 2 && entry.value.chars().all(|c| c.is_ascii_alphabetic()) {
+                countries::unknown_country_message(&entry.value)
+            } else {
+                FieldId::Country.required_message()
+            })
+        } else if let Err(err) = check_subject_entry(entry) {
+            Some(err)
+        } else if entry.attribute == "organizationIdentifier" && !is_valid_organization_identifier(&entry.value) {
+            Some(format!("Invalid organizationIdentifier (expected e.g. VATDE-123456789): {}", entry.value))
+        } else if entry.attribute == "DC" && !entry.value.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            Some(format!("Invalid domain component: {}", entry.value))
+        } else {
+            None
+        };
+        if let Some(message) = problem {
+            issues.push(Issue::error(field, message));
+        }
+    }
+
+    // Transliteration is lossy, say what will end up in the request
+    if config.string_encoding == "ascii" {
+        let raw = match config.custom_subject {
+            Some(custom) => custom.clone(),
+            None => config.field_subject(),
+        };
+        for (i, (raw, written)) in raw.iter().zip(&subject).enumerate() {
+            let transliterated = sanitize_for_cert_field(&raw.value);
+            if !matches!(written.attribute.as_str(), "CN" | "emailAddress") && raw.value != transliterated {
+                issues.push(Issue::warning(field_of(i, &written.attribute), format!("\"{}\" will be written as \"{}\"", raw.value, transliterated)));
+            }
+        }
+    }
+
+    if config.is_ev_request()
+        && let Err((attribute, message)) = validate_ev_subject(&subject) {
+        let field = match config.custom_subject {
+            Some(_) => FieldId::SubjectList,
+            None => FieldId::for_attribute(attribute).unwrap_or(FieldId::RequestProfile),
+        };
+        issues.push(Issue::error(field, message));
+    }
+}
+
+/// Checks the whole form, including inputs that never reach the config like the key passphrase
+pub fn validate_app(app: &CertGenApp) -> Vec<Issue> {
+    let mut issues = validate_config(&CertConfig::from(app));
+
+    if app.reuses_existing_key() {
+        if app.existing_key_path.trim().is_empty() {
+            issues.push(Issue::error(FieldId::ExistingKey, "Path to the existing private key is required"));
+        } else if !std::path::Path::new(&app.existing_key_path).is_file() {
+            issues.push(Issue::error(FieldId::ExistingKey, format!("Private key file {} does not exist", app.existing_key_path)));
+        }
+    } else if app.encrypts_new_key() {
+        if app.key_passphrase != app.key_passphrase_confirm {
+            issues.push(Issue::error(FieldId::KeyPassphrase, "Key passphrases do not match"));
+        }
+        if app.key_passphrase.chars().count() < passphrase::MIN_PASSPHRASE_LEN {
+            issues.push(Issue::error(FieldId::KeyPassphrase, format!("Key passphrase must be at least {} characters", passphrase::MIN_PASSPHRASE_LEN)));
+        }
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cert_config::{SanEntry, SubjectEntry};
+    use crate::cert_config::tests::test_config;
+
+    #[test]
+    fn test_collects_all_issues() {
+        let san = vec![SanEntry::new(SanType::Ip, "not-an-ip"), SanEntry::detect("ok.example.com")];
+        let long_org = "O".repeat(65);
+        let mut config = test_config(&san);
+        config.country = "D1";
+        config.state = "";
+        config.organization = &long_org;
+        config.ca_path_len = "x";
+        config.request_profile = "ca";
+
+        let issues = validate_config(&config);
+        let fields: Vec<FieldId> = issues.iter().filter(|issue| issue.severity == Severity::Error).map(|issue| issue.field).collect();
+        assert_eq!(fields, [FieldId::Country, FieldId::State, FieldId::Organization, FieldId::CaPathLen, FieldId::San(0)]);
+        assert!(has_errors(&issues));
+
+        // The config generator reports all of them
+        let err = config.generate_config().unwrap_err().to_string();
+        assert_eq!(err.lines().count(), 5);
+    }
+
+    #[test]
+    fn test_required_messages_use_labels() {
+        assert_eq!(FieldId::State.required_message(), "State/Province is required");
+        assert_eq!(FieldId::OrganizationalUnit.required_message(), "Organizational Unit is required");
+        assert_eq!(FieldId::JurisdictionLocality.required_message(), "Jurisdiction Locality is required");
+        assert_eq!(FieldId::Subject(2).required_message(), "Subject entry 3 is empty");
+    }
+
+    #[test]
+    fn test_transliteration_warning() {
+        let san = Vec::new();
+        let mut config = test_config(&san);
+
+        let issues = validate_config(&config);
+        assert!(!has_errors(&issues));
+        let warnings = field_issues(&issues, FieldId::Locality);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].severity, Severity::Warning);
+        assert_eq!(warnings[0].message, "\"Münster\" will be written as \"Muenster\"");
+
+        config.string_encoding = "utf8";
+        assert!(validate_config(&config).is_empty());
+    }
+
+    #[test]
+    fn test_country_codes() {
+        let san = Vec::new();
+        let mut config = test_config(&san);
+        config.string_encoding = "utf8";
+
+        config.country = "XX";
+        let issues = validate_config(&config);
+        assert_eq!(issues, [Issue::error(FieldId::Country, "Unknown country code: XX (expected an ISO 3166-1 alpha-2 code)")]);
+
+        // Lower case codes are written upper case
+        config.country = "de";
+        assert!(validate_config(&config).is_empty());
+        assert!(config.generate_config().unwrap().contains("C = DE\n"));
+    }
+
+    #[test]
+    fn test_public_suffix_checks() {
+        let san = vec![
+            SanEntry::detect("www.example.co.uk"),
+            SanEntry::detect("*.co.uk"),
+            SanEntry::detect("co.uk"),
+            SanEntry::detect("*.example.co.uk"),
+        ];
+        let config = test_config(&san);
+        let errors: Vec<FieldId> = validate_config(&config).iter()
+            .filter(|issue| issue.severity == Severity::Error)
+            .map(|issue| issue.field)
+            .collect();
+        assert_eq!(errors, [FieldId::San(1), FieldId::San(2)]);
+
+        let san: Vec<SanEntry> = ["a.com", "www.b.com", "c.de", "d.co.uk", "mail.a.com"].into_iter().map(SanEntry::detect).collect();
+        let config = test_config(&san);
+        let warnings = field_issues(&validate_config(&config), FieldId::SanList)
+            .into_iter().map(|issue| issue.message.clone()).collect::<Vec<_>>();
+        assert_eq!(warnings, ["The SANs span 4 registrable domains (a.com, b.com, c.de, d.co.uk), the CA validates each of them separately"]);
+    }
+
+    #[test]
+    fn test_redundant_sans() {
+        let san = vec![
+            SanEntry::detect("*.example.com"),
+            SanEntry::detect("www.example.com"),
+            SanEntry::detect("Example.com"),
+            SanEntry::detect("example.com"),
+            SanEntry::detect("2001:db8::1"),
+            SanEntry::detect("2001:DB8:0:0::1"),
+        ];
+        let config = test_config(&san);
+        let issues = validate_config(&config);
+        assert!(!has_errors(&issues));
+        let warnings: Vec<(FieldId, &str)> = issues.iter()
+            .filter(|issue| matches!(issue.field, FieldId::San(_)))
+            .map(|issue| (issue.field, issue.message.as_str()))
+            .collect();
+        assert_eq!(warnings, [
+            (FieldId::San(1), "Already matched by the wildcard *.example.com"),
+            (FieldId::San(3), "Same as Example.com after normalization, it is written once"),
+            (FieldId::San(5), "Same as 2001:db8::1 after normalization, it is written once"),
+        ]);
+    }
+
+    #[test]
+    fn test_custom_subject_fields() {
+        let san = Vec::new();
+        let subject = vec![
+            SubjectEntry::new("O", "Test Inc."),
+            SubjectEntry::new("C", "Germany"),
+            SubjectEntry::new("CN", ""),
+            SubjectEntry::new("OU", ""),
+        ];
+        let mut config = test_config(&san);
+        config.custom_subject = Some(&subject);
+        config.common_name = "";
+
+        let fields: Vec<FieldId> = validate_config(&config).iter().map(|issue| issue.field).collect();
+        assert_eq!(fields, [FieldId::CommonName, FieldId::Subject(1), FieldId::Subject(3)]);
+    }
+
+    #[test]
+    fn test_ev_issue_points_to_field() {
+        let san = vec![SanEntry::detect("*.example.com")];
+        let mut config = test_config(&san);
+        config.request_profile = "ev";
+        config.business_category = Some("Private Organization");
+        config.jurisdiction_country = Some("DE");
+
+        let fields: Vec<FieldId> = validate_config(&config).iter()
+            .filter(|issue| issue.severity == Severity::Error)
+            .map(|issue| issue.field)
+            .collect();
+        assert_eq!(fields, [FieldId::SerialNumber, FieldId::San(0)]);
+    }
+}